@@ -0,0 +1,92 @@
+use automaton::{
+  dense_enum, DenseTable, Driver, DriverDenseTable, DriverTransitionTable, StateMachine,
+};
+use criterion::{criterion_group, criterion_main, Criterion};
+use std::{collections::HashMap, hint::black_box};
+
+dense_enum! {
+  #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+  enum State {
+    S0,
+    S1,
+    S2,
+    S3,
+    S4,
+    S5,
+    S6,
+    S7,
+  }
+}
+dense_enum! {
+  #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+  enum Input {
+    A,
+    B,
+    C,
+    D,
+  }
+}
+
+const STATES: [State; 8] = [
+  State::S0,
+  State::S1,
+  State::S2,
+  State::S3,
+  State::S4,
+  State::S5,
+  State::S6,
+  State::S7,
+];
+const INPUTS: [Input; 4] = [Input::A, Input::B, Input::C, Input::D];
+
+/// Complete table, state `s` on input `i` goes to `(3 * s + i + 1) % 8` and outputs `s + i`
+fn transition_table() -> HashMap<(State, Input), (State, usize)> {
+  let mut tt = HashMap::new();
+  for (s, &state) in STATES.iter().enumerate() {
+    for (i, &input) in INPUTS.iter().enumerate() {
+      tt.insert((state, input), (STATES[(3 * s + i + 1) % 8], s + i));
+    }
+  }
+  tt
+}
+
+/// Pseudo-random inputs from a linear congruential generator
+fn inputs(len: usize) -> Vec<Input> {
+  let mut seed = 0x2545_f491_u32;
+  (0..len)
+    .map(|_| {
+      seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12_345);
+      INPUTS[(seed >> 16) as usize % INPUTS.len()]
+    })
+    .collect()
+}
+
+fn bench(c: &mut Criterion) {
+  let tt = transition_table();
+  let dense = DenseTable::from_transition_table(&tt);
+  let inputs = inputs(10_000);
+
+  let mut group = c.benchmark_group("step");
+  group.bench_function("hash_map", |b| {
+    b.iter(|| {
+      let mut sm = StateMachine::new(State::S0);
+      let mut driver = DriverTransitionTable::new(&mut sm, &tt);
+      inputs
+        .iter()
+        .fold(0, |sum, &input| sum + driver.step(black_box(input)))
+    })
+  });
+  group.bench_function("dense", |b| {
+    b.iter(|| {
+      let mut sm = StateMachine::new(State::S0);
+      let mut driver = DriverDenseTable::new(&mut sm, &dense);
+      inputs
+        .iter()
+        .fold(0, |sum, &input| sum + driver.step(black_box(input)))
+    })
+  });
+  group.finish();
+}
+
+criterion_group!(benches, bench);
+criterion_main!(benches);
@@ -0,0 +1,246 @@
+use crate::{Dense, Driver, StateMachine, StepError, TryDriver};
+use std::{collections::HashMap, hash::Hash, marker::PhantomData, ops::Range};
+
+/// Partition of an input alphabet into classes of inputs that behave identically
+///
+/// Two inputs are in the same class if every state has the same transition, or none, for both.
+/// Classes are numbered by their first index and stored as runs over [`Dense`] indices,
+/// so large alphabets like `char` cost memory per run only.
+#[derive(Debug, Clone)]
+pub struct AlphabetClasses<Input> {
+  /// First index and class of every run, consecutive runs have different classes
+  runs: Vec<(usize, usize)>,
+  len: usize,
+  _input: PhantomData<Input>,
+}
+impl<Input: Dense> AlphabetClasses<Input> {
+  /// Classes of a transition table for [`DriverTransitionTable`](crate::DriverTransitionTable)
+  pub fn from_transition_table<State, Output>(tt: &HashMap<(State, Input), (State, Output)>) -> Self
+  where
+    State: Dense + Hash + Eq,
+    Output: Hash + Eq,
+  {
+    let mut signatures: HashMap<usize, Vec<Option<(&State, &Output)>>> = HashMap::new();
+    for ((from, input), (to, output)) in tt {
+      let signature = signatures
+        .entry(input.index())
+        .or_insert_with(|| vec![None; State::COUNT]);
+      signature[from.index()] = Some((to, output));
+    }
+    let mut indices: Vec<usize> = signatures.keys().copied().collect();
+    indices.sort_unstable();
+
+    // unmentioned inputs have no transitions at all
+    let missing = vec![None; State::COUNT];
+    let mut ids: HashMap<&Vec<_>, usize> = HashMap::new();
+    let mut runs: Vec<(usize, usize)> = Vec::new();
+    let mut next = 0;
+    let gaps = indices.iter().map(|&index| (index, &signatures[&index]));
+    for (index, signature) in gaps.chain([(Input::COUNT, &missing)]) {
+      let mut push = |start: usize, signature| {
+        let len = ids.len();
+        let class = *ids.entry(signature).or_insert(len);
+        if runs.last().is_none_or(|&(_, last)| last != class) {
+          runs.push((start, class));
+        }
+      };
+      if next < index {
+        push(next, &missing);
+      }
+      if index < Input::COUNT {
+        push(index, signature);
+      }
+      next = index + 1;
+    }
+    Self {
+      runs,
+      len: ids.len(),
+      _input: PhantomData,
+    }
+  }
+
+  /// Class of `input`
+  pub fn class(&self, input: &Input) -> usize {
+    let index = input.index();
+    let run = self.runs.partition_point(|&(start, _)| start <= index) - 1;
+    self.runs[run].1
+  }
+
+  /// Number of classes
+  pub fn len(&self) -> usize {
+    self.len
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.len == 0
+  }
+
+  /// Maximal runs of consecutive indices with their class, covering `0..Input::COUNT`
+  pub fn ranges(&self) -> impl Iterator<Item = (Range<usize>, usize)> + '_ {
+    let ends = self
+      .runs
+      .iter()
+      .skip(1)
+      .map(|&(start, _)| start)
+      .chain([Input::COUNT]);
+    self
+      .runs
+      .iter()
+      .zip(ends)
+      .map(|(&(start, class), end)| (start..end, class))
+  }
+}
+
+/// Transition table over alphabet classes, indexed by `state * classes + class`
+#[derive(Debug, Clone)]
+pub struct ClassTable<State, Input, Output> {
+  classes: AlphabetClasses<Input>,
+  table: Vec<Option<(State, Output)>>,
+}
+impl<State, Input, Output> ClassTable<State, Input, Output>
+where
+  State: Dense,
+  Input: Dense,
+{
+  /// Compressed copy of a table for [`DriverTransitionTable`](crate::DriverTransitionTable)
+  pub fn from_transition_table(tt: &HashMap<(State, Input), (State, Output)>) -> Self
+  where
+    State: Clone + Hash + Eq,
+    Output: Clone + Hash + Eq,
+  {
+    let classes = AlphabetClasses::from_transition_table(tt);
+    let mut table = vec![None; State::COUNT * classes.len()];
+    for ((from, input), transition) in tt {
+      table[from.index() * classes.len() + classes.class(input)] = Some(transition.clone());
+    }
+    Self { classes, table }
+  }
+
+  pub fn classes(&self) -> &AlphabetClasses<Input> {
+    &self.classes
+  }
+
+  pub fn get(&self, from: &State, input: &Input) -> Option<&(State, Output)> {
+    self.table[from.index() * self.classes.len() + self.classes.class(input)].as_ref()
+  }
+}
+
+/// State machine driver with transition table over alphabet classes
+///
+/// Zero-cost construction
+pub struct DriverClassTable<'a, State, Input, Output> {
+  sm: &'a mut StateMachine<State>,
+  table: &'a ClassTable<State, Input, Output>,
+}
+impl<'a, State, Input, Output> DriverClassTable<'a, State, Input, Output> {
+  pub fn new(sm: &'a mut StateMachine<State>, table: &'a ClassTable<State, Input, Output>) -> Self {
+    Self { sm, table }
+  }
+}
+
+impl<'a, State, Input, Output> Driver<Input, Output> for DriverClassTable<'a, State, Input, Output>
+where
+  State: Dense + Copy,
+  Input: Dense,
+  Output: Copy,
+{
+  fn step(&mut self, input: Input) -> Output {
+    let (state, output) = self.table.get(&self.sm.state, &input).unwrap();
+    self.sm.state = *state;
+    *output
+  }
+}
+
+impl<'a, State, Input, Output> TryDriver<Input, Output>
+  for DriverClassTable<'a, State, Input, Output>
+where
+  State: Dense + Copy,
+  Input: Dense,
+  Output: Copy,
+{
+  type State = State;
+  fn try_step(&mut self, input: Input) -> Result<Output, StepError<State, Input>> {
+    match self.table.get(&self.sm.state, &input) {
+      Some(&(state, output)) => {
+        self.sm.state = state;
+        Ok(output)
+      }
+      None => Err(StepError {
+        state: self.sm.state,
+        input,
+      }),
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{dense_enum, DriverExt, DriverTransitionTable};
+
+  dense_enum! {
+    #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+    enum Token {
+      Start,
+      Word,
+      Number,
+    }
+  }
+
+  /// Splits ASCII text into words and numbers, outputs whether a token ends
+  fn tokenizer<Input: Copy + Hash + Eq>(
+    input: impl Fn(u8) -> Input,
+  ) -> HashMap<(Token, Input), (Token, bool)> {
+    let mut tt = HashMap::new();
+    for from in [Token::Start, Token::Word, Token::Number] {
+      for byte in 0..128u8 {
+        let to = match byte {
+          b'a'..=b'z' | b'A'..=b'Z' => Token::Word,
+          b'0'..=b'9' => Token::Number,
+          _ => Token::Start,
+        };
+        let ends = from != Token::Start && from != to;
+        tt.insert((from, input(byte)), (to, ends));
+      }
+    }
+    tt
+  }
+
+  #[test]
+  fn byte_classes() {
+    let tt = tokenizer(|byte| byte);
+    let table = ClassTable::from_transition_table(&tt);
+    let classes = table.classes();
+    // separators, digits, letters and non-ASCII
+    assert_eq!(classes.len(), 4);
+    assert_eq!(classes.class(&b'a'), classes.class(&b'Z'));
+    assert_ne!(classes.class(&b'a'), classes.class(&b'0'));
+    assert_eq!(classes.class(&b' '), classes.class(&b'_'));
+    assert_eq!(classes.ranges().count(), 8);
+    assert_eq!(classes.ranges().last(), Some((128..256, 3)));
+
+    let text = b"abc 12x9 ".to_vec();
+    let mut state_machine = StateMachine::new(Token::Start);
+    let expected = DriverTransitionTable::new(&mut state_machine, &tt).run(text.clone());
+    let mut state_machine = StateMachine::new(Token::Start);
+    let mut driver = DriverClassTable::new(&mut state_machine, &table);
+    assert_eq!(driver.run(text), expected);
+    assert_eq!(
+      driver.try_step(0xff),
+      Err(StepError {
+        state: Token::Start,
+        input: 0xff
+      })
+    );
+  }
+
+  #[test]
+  fn char_classes() {
+    let tt = tokenizer(char::from);
+    let table = ClassTable::from_transition_table(&tt);
+    assert_eq!(table.classes().len(), 4);
+    assert_eq!(table.classes().ranges().last(), Some((128..0x110000, 3)));
+    assert_eq!(table.get(&Token::Word, &'7'), Some(&(Token::Number, true)));
+    assert_eq!(table.get(&Token::Word, &'λ'), None);
+  }
+}
@@ -0,0 +1,165 @@
+use crate::{Dense, Driver, StateMachine, StepError, TryDriver};
+
+/// Total transition table of `S` states and `I` inputs, indexed by [`Dense`] indices
+///
+/// Built in `const` context without allocation, so it can live in a `static`.
+/// Every `(State, Input)` pair needs an entry, a missing one fails the build:
+///
+/// ```compile_fail
+/// use automaton::{dense_enum, ConstTable};
+/// dense_enum! {
+///   #[derive(Copy, Clone)]
+///   enum State {
+///     Locked,
+///     Unlocked,
+///   }
+/// }
+/// static TURNSTILE: ConstTable<2, 2, State, &str> = ConstTable::new([
+///   [(State::Unlocked, "unlock"), (State::Locked, "blocked")],
+///   [(State::Unlocked, "refund")],
+/// ]);
+/// ```
+///
+/// So does a table whose size differs from `State::COUNT`, and driving it with an input whose `COUNT` differs from `I`:
+///
+/// ```compile_fail
+/// use automaton::{dense_enum, ConstTable};
+/// dense_enum! {
+///   #[derive(Copy, Clone)]
+///   enum State {
+///     Locked,
+///     Unlocked,
+///   }
+/// }
+/// let turnstile: ConstTable<1, 2, State, &str> =
+///   ConstTable::new([[(State::Unlocked, "unlock"), (State::Locked, "blocked")]]);
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct ConstTable<const S: usize, const I: usize, State, Output> {
+  table: [[(State, Output); I]; S],
+}
+impl<const S: usize, const I: usize, State, Output> ConstTable<S, I, State, Output>
+where
+  State: Dense,
+{
+  /// Table with `table[from][input] == (to, output)`
+  pub const fn new(table: [[(State, Output); I]; S]) -> Self {
+    const { assert!(S == State::COUNT, "table rows must match the states") };
+    Self { table }
+  }
+
+  pub const fn table(&self) -> &[[(State, Output); I]; S] {
+    &self.table
+  }
+
+  pub fn get<Input: Dense>(&self, from: &State, input: &Input) -> &(State, Output) {
+    const { assert!(I == Input::COUNT, "table columns must match the inputs") };
+    &self.table[from.index()][input.index()]
+  }
+}
+
+/// State machine driver with compile-time transition table
+///
+/// Needs no allocation, every step succeeds.
+///
+/// Zero-cost construction
+pub struct DriverConstTable<'a, const S: usize, const I: usize, State, Output> {
+  sm: &'a mut StateMachine<State>,
+  table: &'a ConstTable<S, I, State, Output>,
+}
+impl<'a, const S: usize, const I: usize, State, Output> DriverConstTable<'a, S, I, State, Output> {
+  pub fn new(sm: &'a mut StateMachine<State>, table: &'a ConstTable<S, I, State, Output>) -> Self {
+    Self { sm, table }
+  }
+}
+
+impl<'a, const S: usize, const I: usize, State, Input, Output> Driver<Input, Output>
+  for DriverConstTable<'a, S, I, State, Output>
+where
+  State: Dense + Copy,
+  Input: Dense,
+  Output: Copy,
+{
+  fn step(&mut self, input: Input) -> Output {
+    let &(state, output) = self.table.get(&self.sm.state, &input);
+    self.sm.state = state;
+    output
+  }
+}
+
+impl<'a, const S: usize, const I: usize, State, Input, Output> TryDriver<Input, Output>
+  for DriverConstTable<'a, S, I, State, Output>
+where
+  State: Dense + Copy,
+  Input: Dense,
+  Output: Copy,
+{
+  type State = State;
+  fn try_step(&mut self, input: Input) -> Result<Output, StepError<State, Input>> {
+    Ok(self.step(input))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{dense_enum, DriverExt};
+
+  dense_enum! {
+    #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+    enum State {
+      Locked,
+      Unlocked,
+    }
+  }
+  dense_enum! {
+    #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+    enum Input {
+      Coin,
+      Push,
+    }
+  }
+
+  static TURNSTILE: ConstTable<2, 2, State, &str> = ConstTable::new([
+    [(State::Unlocked, "unlock"), (State::Locked, "blocked")],
+    [(State::Unlocked, "refund"), (State::Locked, "lock")],
+  ]);
+
+  #[test]
+  fn static_table() {
+    assert_eq!(TURNSTILE.table()[1][0], (State::Unlocked, "refund"));
+    let inputs = [Input::Push, Input::Coin, Input::Coin, Input::Push];
+    let mut state_machine = StateMachine::new(State::Locked);
+    let mut driver = DriverConstTable::new(&mut state_machine, &TURNSTILE);
+    assert!(driver
+      .drive(inputs)
+      .eq(["blocked", "unlock", "refund", "lock"]));
+    assert_eq!(driver.try_step(Input::Coin), Ok("unlock"));
+    assert_eq!(state_machine.state(), &State::Unlocked);
+  }
+
+  #[cfg(feature = "alloc")]
+  #[test]
+  fn matches_dense_table() {
+    use crate::{DenseTable, DriverDenseTable};
+
+    let inputs = [Input::Push, Input::Coin, Input::Coin, Input::Push];
+    let mut state_machine = StateMachine::new(State::Locked);
+    let trace = DriverConstTable::new(&mut state_machine, &TURNSTILE).run(inputs);
+
+    let mut dense = DenseTable::new();
+    for (from, row) in [State::Locked, State::Unlocked]
+      .into_iter()
+      .zip(TURNSTILE.table())
+    {
+      for (input, &(to, output)) in [Input::Coin, Input::Push].into_iter().zip(row) {
+        dense.insert(from, input, to, output);
+      }
+    }
+    let mut state_machine = StateMachine::new(State::Locked);
+    assert_eq!(
+      DriverDenseTable::new(&mut state_machine, &dense).run(inputs),
+      trace
+    );
+  }
+}
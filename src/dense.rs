@@ -0,0 +1,258 @@
+#[cfg(feature = "alloc")]
+use crate::{Driver, StateMachine, StepError, TryDriver};
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+#[cfg(feature = "alloc")]
+use core::marker::PhantomData;
+#[cfg(feature = "std")]
+use std::collections::HashMap;
+
+/// Types with a bijection onto `0..COUNT`
+///
+/// Implemented for `bool`, `u8` and `char`, and by [`dense_enum!`](crate::dense_enum) for field-less enums.
+pub trait Dense {
+  const COUNT: usize;
+  fn index(&self) -> usize;
+}
+
+impl Dense for bool {
+  const COUNT: usize = 2;
+  fn index(&self) -> usize {
+    *self as usize
+  }
+}
+
+impl Dense for u8 {
+  const COUNT: usize = 256;
+  fn index(&self) -> usize {
+    *self as usize
+  }
+}
+
+/// Unicode scalar values, surrogate indices are never used
+impl Dense for char {
+  const COUNT: usize = 0x110000;
+  fn index(&self) -> usize {
+    *self as usize
+  }
+}
+
+/// Declares a field-less enum implementing [`Dense`] in declaration order
+///
+/// ```
+/// automaton::dense_enum! {
+///   #[derive(Debug, Copy, Clone, PartialEq, Eq)]
+///   pub enum Input {
+///     Coin,
+///     Push,
+///   }
+/// }
+/// use automaton::Dense;
+/// assert_eq!((Input::COUNT, Input::Push.index()), (2, 1));
+/// ```
+#[macro_export]
+macro_rules! dense_enum {
+  ($(#[$meta:meta])* $vis:vis enum $name:ident { $($variant:ident),* $(,)? }) => {
+    $(#[$meta])*
+    $vis enum $name {
+      $($variant),*
+    }
+    impl $crate::Dense for $name {
+      const COUNT: usize = [$($name::$variant),*].len();
+      fn index(&self) -> usize {
+        match self {
+          $($name::$variant => $name::$variant as usize),*
+        }
+      }
+    }
+  };
+}
+
+/// Transition table in a flat vector indexed by `state * Input::COUNT + input`
+#[cfg(feature = "alloc")]
+#[derive(Debug, Clone)]
+pub struct DenseTable<State, Input, Output> {
+  table: Vec<Option<(State, Output)>>,
+  _input: PhantomData<Input>,
+}
+#[cfg(feature = "alloc")]
+impl<State, Input, Output> DenseTable<State, Input, Output>
+where
+  State: Dense,
+  Input: Dense,
+{
+  /// Table without transitions
+  pub fn new() -> Self {
+    Self {
+      table: core::iter::repeat_with(|| None)
+        .take(State::COUNT * Input::COUNT)
+        .collect(),
+      _input: PhantomData,
+    }
+  }
+
+  fn slot(from: &State, input: &Input) -> usize {
+    from.index() * Input::COUNT + input.index()
+  }
+
+  /// Sets the transition for `(from, input)`, returning the previous one
+  pub fn insert(
+    &mut self,
+    from: State,
+    input: Input,
+    to: State,
+    output: Output,
+  ) -> Option<(State, Output)> {
+    self.table[Self::slot(&from, &input)].replace((to, output))
+  }
+
+  pub fn get(&self, from: &State, input: &Input) -> Option<&(State, Output)> {
+    self.table[Self::slot(from, input)].as_ref()
+  }
+
+  /// Dense copy of a table for [`DriverTransitionTable`](crate::DriverTransitionTable)
+  #[cfg(feature = "std")]
+  pub fn from_transition_table(tt: &HashMap<(State, Input), (State, Output)>) -> Self
+  where
+    State: Clone,
+    Output: Clone,
+  {
+    let mut table = Self::new();
+    for ((from, input), (to, output)) in tt {
+      table.table[Self::slot(from, input)] = Some((to.clone(), output.clone()));
+    }
+    table
+  }
+}
+#[cfg(feature = "alloc")]
+impl<State: Dense, Input: Dense, Output> Default for DenseTable<State, Input, Output> {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+/// State machine driver with dense transition table
+///
+/// Zero-cost construction
+#[cfg(feature = "alloc")]
+pub struct DriverDenseTable<'a, State, Input, Output> {
+  sm: &'a mut StateMachine<State>,
+  table: &'a DenseTable<State, Input, Output>,
+}
+#[cfg(feature = "alloc")]
+impl<'a, State, Input, Output> DriverDenseTable<'a, State, Input, Output> {
+  pub fn new(sm: &'a mut StateMachine<State>, table: &'a DenseTable<State, Input, Output>) -> Self {
+    Self { sm, table }
+  }
+}
+
+#[cfg(feature = "alloc")]
+impl<'a, State, Input, Output> Driver<Input, Output> for DriverDenseTable<'a, State, Input, Output>
+where
+  State: Dense + Copy,
+  Input: Dense,
+  Output: Copy,
+{
+  fn step(&mut self, input: Input) -> Output {
+    let (state, output) = self.table.get(&self.sm.state, &input).unwrap();
+    self.sm.state = *state;
+    *output
+  }
+}
+
+#[cfg(feature = "alloc")]
+impl<'a, State, Input, Output> TryDriver<Input, Output>
+  for DriverDenseTable<'a, State, Input, Output>
+where
+  State: Dense + Copy,
+  Input: Dense,
+  Output: Copy,
+{
+  type State = State;
+  fn try_step(&mut self, input: Input) -> Result<Output, StepError<State, Input>> {
+    match self.table.get(&self.sm.state, &input) {
+      Some(&(state, output)) => {
+        self.sm.state = state;
+        Ok(output)
+      }
+      None => Err(StepError {
+        state: self.sm.state,
+        input,
+      }),
+    }
+  }
+}
+
+#[cfg(all(test, feature = "alloc"))]
+mod tests {
+  use super::*;
+  use crate::DriverExt;
+
+  dense_enum! {
+    #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+    enum State {
+      Locked,
+      Unlocked,
+    }
+  }
+  dense_enum! {
+    #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+    enum Input {
+      Push,
+      Coin,
+      Kick,
+    }
+  }
+
+  const TRANSITIONS: [(State, Input, State, &str); 4] = [
+    (State::Locked, Input::Push, State::Locked, "blocked"),
+    (State::Locked, Input::Coin, State::Unlocked, "unlock"),
+    (State::Unlocked, Input::Coin, State::Unlocked, "refund"),
+    (State::Unlocked, Input::Push, State::Locked, "lock"),
+  ];
+  const INPUTS: [Input; 4] = [Input::Coin, Input::Coin, Input::Push, Input::Push];
+
+  #[test]
+  fn dense_driver() {
+    assert_eq!((State::COUNT, Input::COUNT), (2, 3));
+    assert_eq!(Input::Kick.index(), 2);
+    let mut table = DenseTable::new();
+    for (from, input, to, output) in TRANSITIONS {
+      assert_eq!(table.insert(from, input, to, output), None);
+    }
+    let mut state_machine = StateMachine::new(State::Locked);
+    let mut driver = DriverDenseTable::new(&mut state_machine, &table);
+    assert_eq!(driver.run(INPUTS), ["unlock", "refund", "lock", "blocked"]);
+    assert_eq!(
+      driver.try_step(Input::Kick),
+      Err(StepError {
+        state: State::Locked,
+        input: Input::Kick
+      })
+    );
+
+    let mut bytes = DenseTable::<bool, u8, ()>::new();
+    assert_eq!(bytes.insert(false, b'a', true, ()), None);
+    assert_eq!(bytes.insert(false, b'a', false, ()), Some((true, ())));
+    assert_eq!(bytes.get(&false, &b'a'), Some(&(false, ())));
+    assert_eq!(bytes.get(&true, &b'a'), None);
+  }
+
+  #[cfg(feature = "std")]
+  #[test]
+  fn matches_hash_table() {
+    use crate::DriverTransitionTable;
+
+    let transition_table: HashMap<_, _> = TRANSITIONS
+      .into_iter()
+      .map(|(from, input, to, output)| ((from, input), (to, output)))
+      .collect();
+    let mut state_machine = StateMachine::new(State::Locked);
+    let expected = DriverTransitionTable::new(&mut state_machine, &transition_table).run(INPUTS);
+
+    let table = DenseTable::from_transition_table(&transition_table);
+    let mut state_machine = StateMachine::new(State::Locked);
+    let mut driver = DriverDenseTable::new(&mut state_machine, &table);
+    assert_eq!(driver.run(INPUTS), expected);
+  }
+}
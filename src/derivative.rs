@@ -0,0 +1,204 @@
+use crate::{Driver, Regex};
+use std::{collections::HashMap, hash::Hash};
+
+impl<Symbol: Clone + Ord> Regex<Symbol> {
+  /// Brzozowski derivative, the words `w` such that `symbol w` matches
+  ///
+  /// Built with the simplifying constructors, so derivatives of a simplified regex
+  /// are equal whenever they are similar.
+  pub fn derivative(&self, symbol: &Symbol) -> Self {
+    match self {
+      Regex::Empty | Regex::Epsilon => Regex::Empty,
+      Regex::Symbol(other) if other == symbol => Regex::Epsilon,
+      Regex::Symbol(_) => Regex::Empty,
+      Regex::Class(symbols) if symbols.contains(symbol) => Regex::Epsilon,
+      Regex::Class(_) => Regex::Empty,
+      Regex::Concat(regexes) => {
+        let Some((first, rest)) = regexes.split_first() else {
+          return Regex::Empty;
+        };
+        let rest = Regex::concat(rest.iter().cloned());
+        let derivative = Regex::concat([first.derivative(symbol), rest.clone()]);
+        match first.is_nullable() {
+          true => Regex::alternation([derivative, rest.derivative(symbol)]),
+          false => derivative,
+        }
+      }
+      Regex::Alternation(regexes) => {
+        Regex::alternation(regexes.iter().map(|regex| regex.derivative(symbol)))
+      }
+      Regex::Star(regex) | Regex::Plus(regex) => {
+        Regex::concat([regex.derivative(symbol), Regex::star((**regex).clone())])
+      }
+      Regex::Optional(regex) => regex.derivative(symbol),
+      // at most zero repetitions only match the empty word
+      Regex::Repeat { max: Some(0), .. } => Regex::Empty,
+      Regex::Repeat { regex, min, max } => {
+        let rest = Regex::repeat(
+          (**regex).clone(),
+          min.saturating_sub(1),
+          max.map(|max| max - 1),
+        );
+        Regex::concat([regex.derivative(symbol), rest])
+      }
+    }
+  }
+}
+
+/// DFA constructed on the fly from Brzozowski derivatives
+///
+/// Every state is a derivative of the regex. Discovered states and transitions are cached,
+/// once the cache holds `capacity` states it is cleared and refilled on demand.
+/// Outputs whether the entered state is accepting.
+pub struct LazyDfa<Symbol> {
+  initial: Regex<Symbol>,
+  capacity: usize,
+  states: Vec<(Regex<Symbol>, bool)>,
+  ids: HashMap<Regex<Symbol>, usize>,
+  transitions: HashMap<(usize, Symbol), usize>,
+  current: usize,
+}
+impl<Symbol> LazyDfa<Symbol>
+where
+  Symbol: Clone + Hash + Ord,
+{
+  pub fn new(regex: &Regex<Symbol>, capacity: usize) -> Self {
+    let mut dfa = Self {
+      initial: regex.simplify(),
+      capacity: capacity.max(2),
+      states: Vec::new(),
+      ids: HashMap::new(),
+      transitions: HashMap::new(),
+      current: 0,
+    };
+    dfa.clear();
+    dfa
+  }
+
+  fn clear(&mut self) {
+    self.states.clear();
+    self.ids.clear();
+    self.transitions.clear();
+    self.intern(self.initial.clone());
+  }
+
+  fn intern(&mut self, regex: Regex<Symbol>) -> usize {
+    if let Some(&id) = self.ids.get(&regex) {
+      return id;
+    }
+    self.ids.insert(regex.clone(), self.states.len());
+    let nullable = regex.is_nullable();
+    self.states.push((regex, nullable));
+    self.states.len() - 1
+  }
+
+  /// Back to the initial state, keeping the cache
+  pub fn reset(&mut self) {
+    self.current = self.ids[&self.initial];
+  }
+
+  /// Derivative the current state stands for
+  pub fn state(&self) -> &Regex<Symbol> {
+    &self.states[self.current].0
+  }
+
+  pub fn is_accepting(&self) -> bool {
+    self.states[self.current].1
+  }
+
+  /// Number of states currently cached
+  pub fn cached_states(&self) -> usize {
+    self.states.len()
+  }
+
+  /// Matches `word` from the initial state and stays in the reached state
+  pub fn accepts<Word>(&mut self, word: Word) -> bool
+  where
+    Word: IntoIterator<Item = Symbol>,
+  {
+    self.reset();
+    word.into_iter().for_each(|symbol| {
+      self.step(symbol);
+    });
+    self.is_accepting()
+  }
+}
+
+impl<Symbol> Driver<Symbol, bool> for LazyDfa<Symbol>
+where
+  Symbol: Clone + Hash + Ord,
+{
+  fn step(&mut self, input: Symbol) -> bool {
+    let key = (self.current, input);
+    if let Some(&next) = self.transitions.get(&key) {
+      self.current = next;
+      return self.is_accepting();
+    }
+
+    let derivative = self.states[key.0].0.derivative(&key.1);
+    if !self.ids.contains_key(&derivative) && self.states.len() >= self.capacity {
+      self.clear();
+      self.current = self.intern(derivative);
+    } else {
+      self.current = self.intern(derivative);
+      self.transitions.insert(key, self.current);
+    }
+    self.is_accepting()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{DriverDfa, DriverExt, StateMachine};
+
+  #[test]
+  fn derivatives() {
+    let regex = Regex::<char>::parse("(ab)*c").unwrap().simplify();
+    assert_eq!(regex.derivative(&'a').to_string(), "b(ab)*c");
+    assert_eq!(regex.derivative(&'c'), Regex::Epsilon);
+    assert_eq!(regex.derivative(&'b'), Regex::Empty);
+    let regex = Regex::<char>::parse("a{2,3}").unwrap();
+    assert_eq!(regex.derivative(&'a').to_string(), "a{1,2}");
+    assert_eq!(regex.derivative(&'a').derivative(&'a').to_string(), "a?");
+    let regex = Regex::<char>::parse("a{0}").unwrap();
+    assert_eq!(regex.derivative(&'a'), Regex::Empty);
+  }
+
+  #[test]
+  fn lazy_matches_compiled() {
+    let regex = Regex::<char>::parse("(a|b)*abb(a|b){0,2}").unwrap();
+    let dfa = regex.to_dfa();
+    let input = "abbabababbbaabbbabba";
+
+    let mut state_machine = StateMachine::new(*dfa.initial());
+    let expected = DriverDfa::new(&mut state_machine, &dfa).run(input.chars());
+    let mut lazy = LazyDfa::new(&regex, 64);
+    assert_eq!(lazy.run(input.chars()), expected);
+    let cached = lazy.cached_states();
+
+    let mut bounded = LazyDfa::new(&regex, 3);
+    assert_eq!(bounded.run(input.chars()), expected);
+    assert!(bounded.cached_states() <= 3);
+
+    assert!(lazy.accepts("babb".chars()));
+    assert!(!lazy.accepts("bab".chars()));
+    assert_eq!(lazy.cached_states(), cached);
+  }
+
+  #[test]
+  fn lazy_matches_thompson() {
+    for pattern in ["a*a", "a{0}b", "(ab){0,0}|a*a"] {
+      let regex = Regex::<char>::parse(pattern).unwrap();
+      let dfa = regex.thompson().determinize().0;
+      let mut lazy = LazyDfa::new(&regex, 64);
+      for word in ["", "a", "aa", "b", "ab"] {
+        assert_eq!(
+          lazy.accepts(word.chars()),
+          dfa.accepts(word.chars()),
+          "{pattern} on {word}"
+        );
+      }
+    }
+  }
+}
@@ -0,0 +1,518 @@
+use crate::{partition::hopcroft, Driver, StateMachine, StepError, TryDriver};
+use std::{
+  collections::{HashMap, HashSet},
+  hash::Hash,
+};
+
+/// Deterministic finite acceptor
+///
+/// The transition table may be partial, a missing transition rejects.
+#[derive(Debug, Clone)]
+#[cfg_attr(
+  feature = "serde",
+  derive(serde::Serialize, serde::Deserialize),
+  serde(bound(
+    serialize = "State: serde::Serialize, Symbol: serde::Serialize",
+    deserialize = "State: serde::Deserialize<'de> + Hash + Eq, Symbol: serde::Deserialize<'de> + Hash + Eq"
+  ))
+)]
+pub struct Dfa<State, Symbol> {
+  initial: State,
+  #[cfg_attr(feature = "serde", serde(with = "crate::serde_table::acceptor"))]
+  transitions: HashMap<(State, Symbol), State>,
+  accepting: HashSet<State>,
+}
+impl<State, Symbol> Dfa<State, Symbol> {
+  pub fn new(
+    initial: State,
+    transitions: HashMap<(State, Symbol), State>,
+    accepting: HashSet<State>,
+  ) -> Self {
+    Self {
+      initial,
+      transitions,
+      accepting,
+    }
+  }
+
+  pub fn initial(&self) -> &State {
+    &self.initial
+  }
+
+  pub fn transitions(&self) -> &HashMap<(State, Symbol), State> {
+    &self.transitions
+  }
+
+  pub fn accepting(&self) -> &HashSet<State> {
+    &self.accepting
+  }
+}
+
+impl<State, Symbol> Dfa<State, Symbol>
+where
+  State: Copy + Hash + Eq,
+  Symbol: Hash + Eq,
+{
+  /// Drops the outputs of a transducer table and keeps its states as acceptor
+  pub fn from_transition_table<Output>(
+    initial: State,
+    tt: &HashMap<(State, Symbol), (State, Output)>,
+    accepting: HashSet<State>,
+  ) -> Self
+  where
+    Symbol: Clone,
+  {
+    let transitions = tt
+      .iter()
+      .map(|((from, symbol), (to, _))| ((*from, symbol.clone()), *to))
+      .collect();
+    Self::new(initial, transitions, accepting)
+  }
+
+  pub fn is_accepting(&self, state: &State) -> bool {
+    self.accepting.contains(state)
+  }
+
+  /// Follows `word` starting in `state`, `None` if a transition is missing
+  pub fn run_from<Word>(&self, state: State, word: Word) -> Option<State>
+  where
+    Word: IntoIterator<Item = Symbol>,
+  {
+    word.into_iter().try_fold(state, |state, symbol| {
+      self.transitions.get(&(state, symbol)).copied()
+    })
+  }
+
+  pub fn accepts<Word>(&self, word: Word) -> bool
+  where
+    Word: IntoIterator<Item = Symbol>,
+  {
+    self
+      .run_from(self.initial, word)
+      .is_some_and(|state| self.is_accepting(&state))
+  }
+
+  /// Minimal equivalent DFA using Hopcroft's algorithm
+  ///
+  /// Unreachable states and states that cannot reach an accepting state are dropped,
+  /// the returned mapping only contains the remaining states.
+  pub fn minimize(&self) -> (Dfa<usize, Symbol>, HashMap<State, usize>)
+  where
+    Symbol: Clone,
+  {
+    let mut outgoing: HashMap<State, Vec<_>> = HashMap::new();
+    for ((from, symbol), to) in &self.transitions {
+      outgoing.entry(*from).or_default().push((symbol, to));
+    }
+    let mut states = vec![self.initial];
+    let mut indices = HashMap::from([(self.initial, 0)]);
+    let mut alphabet = Vec::new();
+    let mut symbol_indices = HashMap::new();
+    let mut edges = Vec::new();
+    let mut next = 0;
+    while next < states.len() {
+      for &(symbol, to) in outgoing.get(&states[next]).into_iter().flatten() {
+        let symbol = *symbol_indices.entry(symbol).or_insert_with(|| {
+          alphabet.push(symbol.clone());
+          alphabet.len() - 1
+        });
+        let to = *indices.entry(*to).or_insert_with(|| {
+          states.push(*to);
+          states.len() - 1
+        });
+        edges.push((next, symbol, to));
+      }
+      next += 1;
+    }
+
+    // complete with a sink state
+    let (n, k, sink) = (states.len() + 1, alphabet.len(), states.len());
+    let mut delta = vec![sink; n * k];
+    for &(from, symbol, to) in &edges {
+      delta[from * k + symbol] = to;
+    }
+    let mut classes: Vec<usize> = states
+      .iter()
+      .map(|state| self.is_accepting(state) as usize)
+      .collect();
+    classes.push(0);
+    let blocks = hopcroft(n, k, &delta, &classes);
+
+    let dead = blocks[sink];
+    let mut renumber = HashMap::new();
+    for &block in &blocks[..sink] {
+      if block != dead || blocks[0] == dead {
+        let len = renumber.len();
+        renumber.entry(block).or_insert(len);
+      }
+    }
+    let transitions = edges
+      .iter()
+      .filter(|&&(_, _, to)| blocks[to] != dead)
+      .map(|&(from, symbol, to)| {
+        let key = (renumber[&blocks[from]], alphabet[symbol].clone());
+        (key, renumber[&blocks[to]])
+      })
+      .collect();
+    let accepting = (0..sink)
+      .filter(|&state| classes[state] == 1)
+      .map(|state| renumber[&blocks[state]])
+      .collect();
+    let mapping = states
+      .iter()
+      .enumerate()
+      .filter_map(|(index, state)| Some((*state, *renumber.get(&blocks[index])?)))
+      .collect();
+    (Dfa::new(0, transitions, accepting), mapping)
+  }
+}
+
+/// Boolean operations
+///
+/// Results are complete over the union of the alphabets,
+/// `None` is the sink state of an operand without a transition.
+impl<State, Symbol> Dfa<State, Symbol>
+where
+  State: Copy + Hash + Eq,
+  Symbol: Clone + Hash + Eq,
+{
+  /// Symbols with at least one transition
+  pub fn alphabet(&self) -> HashSet<Symbol> {
+    self
+      .transitions
+      .keys()
+      .map(|(_, symbol)| symbol.clone())
+      .collect()
+  }
+
+  /// Adds the sink state `None` for every missing transition over `alphabet`
+  pub fn complete<Alphabet>(&self, alphabet: Alphabet) -> Dfa<Option<State>, Symbol>
+  where
+    Alphabet: IntoIterator<Item = Symbol>,
+  {
+    let alphabet: HashSet<Symbol> = alphabet.into_iter().chain(self.alphabet()).collect();
+    let states: HashSet<Option<State>> = self
+      .transitions
+      .iter()
+      .flat_map(|((from, _), to)| [Some(*from), Some(*to)])
+      .chain([Some(self.initial), None])
+      .collect();
+    let mut transitions = HashMap::new();
+    for state in states {
+      for symbol in &alphabet {
+        let to = state.and_then(|state| self.transitions.get(&(state, symbol.clone())).copied());
+        transitions.insert((state, symbol.clone()), to);
+      }
+    }
+    let accepting = self.accepting.iter().map(|state| Some(*state)).collect();
+    Dfa::new(Some(self.initial), transitions, accepting)
+  }
+
+  /// Accepts exactly the words over the alphabet of `self` it rejected before
+  pub fn complement(&self) -> Dfa<Option<State>, Symbol> {
+    let complete = self.complete([]);
+    let accepting = complete
+      .transitions
+      .keys()
+      .map(|(state, _)| *state)
+      .chain([complete.initial])
+      .filter(|state| !complete.is_accepting(state))
+      .collect();
+    Dfa::new(complete.initial, complete.transitions, accepting)
+  }
+
+  /// Reachable part of the product automaton
+  ///
+  /// A pair of states is accepting if `accept` holds for the acceptance of its components.
+  pub fn product<Other, F>(
+    &self,
+    other: &Dfa<Other, Symbol>,
+    accept: F,
+  ) -> Dfa<(Option<State>, Option<Other>), Symbol>
+  where
+    Other: Copy + Hash + Eq,
+    F: Fn(bool, bool) -> bool,
+  {
+    let alphabet: Vec<Symbol> = self
+      .alphabet()
+      .into_iter()
+      .chain(other.alphabet())
+      .collect::<HashSet<_>>()
+      .into_iter()
+      .collect();
+    let initial = (Some(self.initial), Some(other.initial));
+    let mut states = vec![initial];
+    let mut visited = HashSet::from([initial]);
+    let mut transitions = HashMap::new();
+    let mut accepting = HashSet::new();
+    while let Some(pair @ (a, b)) = states.pop() {
+      if accept(
+        a.is_some_and(|a| self.is_accepting(&a)),
+        b.is_some_and(|b| other.is_accepting(&b)),
+      ) {
+        accepting.insert(pair);
+      }
+      for symbol in &alphabet {
+        let to = (
+          a.and_then(|a| self.transitions.get(&(a, symbol.clone())).copied()),
+          b.and_then(|b| other.transitions.get(&(b, symbol.clone())).copied()),
+        );
+        if visited.insert(to) {
+          states.push(to);
+        }
+        transitions.insert((pair, symbol.clone()), to);
+      }
+    }
+    Dfa::new(initial, transitions, accepting)
+  }
+
+  pub fn union<Other>(
+    &self,
+    other: &Dfa<Other, Symbol>,
+  ) -> Dfa<(Option<State>, Option<Other>), Symbol>
+  where
+    Other: Copy + Hash + Eq,
+  {
+    self.product(other, |a, b| a || b)
+  }
+
+  pub fn intersection<Other>(
+    &self,
+    other: &Dfa<Other, Symbol>,
+  ) -> Dfa<(Option<State>, Option<Other>), Symbol>
+  where
+    Other: Copy + Hash + Eq,
+  {
+    self.product(other, |a, b| a && b)
+  }
+
+  pub fn difference<Other>(
+    &self,
+    other: &Dfa<Other, Symbol>,
+  ) -> Dfa<(Option<State>, Option<Other>), Symbol>
+  where
+    Other: Copy + Hash + Eq,
+  {
+    self.product(other, |a, b| a && !b)
+  }
+
+  pub fn symmetric_difference<Other>(
+    &self,
+    other: &Dfa<Other, Symbol>,
+  ) -> Dfa<(Option<State>, Option<Other>), Symbol>
+  where
+    Other: Copy + Hash + Eq,
+  {
+    self.product(other, |a, b| a != b)
+  }
+}
+
+/// State machine driver with deterministic finite acceptor
+///
+/// Outputs whether the entered state is accepting.
+/// Zero-cost construction
+pub struct DriverDfa<'a, State, Symbol> {
+  sm: &'a mut StateMachine<State>,
+  dfa: &'a Dfa<State, Symbol>,
+}
+impl<'a, State, Symbol> DriverDfa<'a, State, Symbol>
+where
+  State: Copy + Hash + Eq,
+  Symbol: Hash + Eq,
+{
+  pub fn new(sm: &'a mut StateMachine<State>, dfa: &'a Dfa<State, Symbol>) -> Self {
+    Self { sm, dfa }
+  }
+
+  pub fn is_accepting(&self) -> bool {
+    self.dfa.is_accepting(&self.sm.state)
+  }
+}
+
+impl<'a, State, Symbol> Driver<Symbol, bool> for DriverDfa<'a, State, Symbol>
+where
+  State: Copy + Hash + Eq,
+  Symbol: Hash + Eq,
+{
+  fn step(&mut self, input: Symbol) -> bool {
+    let state = self.dfa.transitions.get(&(self.sm.state, input)).unwrap();
+    self.sm.state = *state;
+    self.is_accepting()
+  }
+}
+
+impl<'a, State, Symbol> TryDriver<Symbol, bool> for DriverDfa<'a, State, Symbol>
+where
+  State: Copy + Hash + Eq,
+  Symbol: Hash + Eq,
+{
+  type State = State;
+  fn try_step(&mut self, input: Symbol) -> Result<bool, StepError<State, Symbol>> {
+    let key = (self.sm.state, input);
+    match self.dfa.transitions.get(&key) {
+      Some(state) => {
+        self.sm.state = *state;
+        Ok(self.is_accepting())
+      }
+      None => Err(StepError {
+        state: key.0,
+        input: key.1,
+      }),
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  /// Binary numbers divisible by three, most significant bit first
+  fn divisible_by_three() -> Dfa<u8, char> {
+    let transitions = HashMap::from([
+      ((0, '0'), 0),
+      ((0, '1'), 1),
+      ((1, '0'), 2),
+      ((1, '1'), 0),
+      ((2, '0'), 1),
+      ((2, '1'), 2),
+    ]);
+    Dfa::new(0, transitions, HashSet::from([0]))
+  }
+
+  #[test]
+  fn divisible_by_three_accepts() {
+    let dfa = divisible_by_three();
+    assert!(dfa.accepts("".chars()));
+    assert!(dfa.accepts("11".chars()));
+    assert!(dfa.accepts("1001".chars()));
+    assert!(!dfa.accepts("111".chars()));
+    assert!(!dfa.accepts("12".chars()));
+    assert_eq!(dfa.run_from(1, "0".chars()), Some(2));
+    assert_eq!(dfa.run_from(1, "x".chars()), None);
+  }
+
+  #[test]
+  fn divisible_by_three_driver() {
+    let dfa = divisible_by_three();
+    let mut state_machine = StateMachine::new(*dfa.initial());
+    let mut driver = DriverDfa::new(&mut state_machine, &dfa);
+    assert!(driver.is_accepting());
+    assert!(!driver.step('1'));
+    assert!(driver.step('1'));
+    assert_eq!(
+      driver.try_step('2'),
+      Err(StepError {
+        state: 0,
+        input: '2'
+      })
+    );
+    assert!(driver.is_accepting());
+  }
+
+  #[test]
+  fn turnstile_from_transition_table() {
+    #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+    enum State {
+      Locked,
+      Unlocked,
+    }
+    #[derive(Clone, PartialEq, Eq, Hash)]
+    enum Input {
+      Push,
+      Coin,
+    }
+    let transition_table = HashMap::from([
+      ((State::Locked, Input::Push), (State::Locked, ())),
+      ((State::Locked, Input::Coin), (State::Unlocked, ())),
+      ((State::Unlocked, Input::Coin), (State::Unlocked, ())),
+      ((State::Unlocked, Input::Push), (State::Locked, ())),
+    ]);
+
+    let dfa = Dfa::from_transition_table(
+      State::Locked,
+      &transition_table,
+      HashSet::from([State::Unlocked]),
+    );
+    assert!(dfa.accepts([Input::Push, Input::Coin]));
+    assert!(!dfa.accepts([Input::Coin, Input::Push]));
+  }
+
+  #[test]
+  fn divisible_by_three_minimize() {
+    // remainder modulo six of binary numbers, accepting remainders 0 and 3
+    let mut transitions = HashMap::new();
+    for state in 0..6u8 {
+      transitions.insert((state, '0'), state * 2 % 6);
+      transitions.insert((state, '1'), (state * 2 + 1) % 6);
+    }
+    // unreachable and dead states
+    transitions.insert((6, '0'), 0);
+    transitions.insert((0, 'x'), 7);
+    transitions.insert((7, '0'), 7);
+    let dfa = Dfa::new(0, transitions, HashSet::from([0, 3]));
+
+    let (minimal, mapping) = dfa.minimize();
+    assert_eq!(minimal.transitions().len(), 6);
+    assert_eq!(mapping.len(), 6);
+    assert_eq!(mapping[&0], 0);
+    assert_eq!(mapping[&0], mapping[&3]);
+    assert_eq!(mapping[&1], mapping[&4]);
+    assert_eq!(mapping[&2], mapping[&5]);
+    assert_ne!(mapping[&1], mapping[&2]);
+    for word in ["", "11", "110", "1001", "111", "10", "0x0"] {
+      assert_eq!(minimal.accepts(word.chars()), dfa.accepts(word.chars()));
+    }
+  }
+
+  #[test]
+  fn boolean_operations() {
+    // even number of a's
+    let even = Dfa::new(
+      0u8,
+      HashMap::from([((0, 'a'), 1), ((1, 'a'), 0), ((0, 'b'), 0), ((1, 'b'), 1)]),
+      HashSet::from([0]),
+    );
+    // ends with b
+    let ends_with_b = Dfa::new(
+      false,
+      HashMap::from([
+        ((false, 'a'), false),
+        ((false, 'b'), true),
+        ((true, 'a'), false),
+        ((true, 'b'), true),
+      ]),
+      HashSet::from([true]),
+    );
+    // exactly "a"
+    let single_a = Dfa::new(0u8, HashMap::from([((0, 'a'), 1)]), HashSet::from([1]));
+
+    let union = even.union(&ends_with_b);
+    let intersection = even.intersection(&ends_with_b);
+    let difference = even.difference(&ends_with_b);
+    let symmetric_difference = even.symmetric_difference(&ends_with_b);
+    for word in ["", "a", "b", "ab", "aab", "aba", "abab"] {
+      let (a, b) = (
+        even.accepts(word.chars()),
+        ends_with_b.accepts(word.chars()),
+      );
+      assert_eq!(union.accepts(word.chars()), a || b);
+      assert_eq!(intersection.accepts(word.chars()), a && b);
+      assert_eq!(difference.accepts(word.chars()), a && !b);
+      assert_eq!(symmetric_difference.accepts(word.chars()), a != b);
+    }
+
+    let complement = single_a.complement();
+    assert!(complement.accepts("".chars()));
+    assert!(!complement.accepts("a".chars()));
+    assert!(complement.accepts("aa".chars()));
+    assert!(!complement.accepts("b".chars()));
+    assert!(single_a.complete(['b']).complement().accepts("b".chars()));
+
+    let intersection = single_a.intersection(&even.complement());
+    let mut state_machine = StateMachine::new(*intersection.initial());
+    let mut driver = DriverDfa::new(&mut state_machine, &intersection);
+    assert!(driver.step('a'));
+    assert!(!driver.step('b'));
+    assert!(!driver.step('a'));
+  }
+}
@@ -0,0 +1,884 @@
+//! Graphviz DOT export and import
+//!
+//! Nodes are labelled with their state, edges with `input / output` for transducers
+//! and with the symbol for acceptors. Parallel edges are merged into one edge
+//! with one label line per transition. The initial state is marked by an arrow
+//! from an invisible point, accepting states are drawn as double circles.
+
+use crate::{Dfa, Mealy, Nfa};
+use std::{
+  collections::{hash_map::Entry, BTreeMap, HashMap},
+  error::Error,
+  fmt::{self, Display, Write},
+  hash::Hash,
+  iter::Peekable,
+  str::{Chars, FromStr},
+};
+
+/// Epsilon move label
+const EPSILON: &str = "ε";
+
+fn escape(label: &str) -> String {
+  label.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+/// Renders a graph, nodes and merged edges sorted by label
+fn render<'a, State, States, Edges>(
+  states: States,
+  initial: Option<&'a State>,
+  accepting: impl Fn(&State) -> bool,
+  edges: Edges,
+  state_label: impl Fn(&State) -> String,
+) -> String
+where
+  State: Hash + Eq + 'a,
+  States: IntoIterator<Item = &'a State>,
+  Edges: IntoIterator<Item = (&'a State, String, &'a State)>,
+{
+  let mut nodes: Vec<(String, &State)> = Vec::new();
+  let mut ids = HashMap::new();
+  for state in states.into_iter().chain(initial) {
+    if ids.insert(state, 0).is_none() {
+      nodes.push((state_label(state), state));
+    }
+  }
+  nodes.sort_by(|a, b| a.0.cmp(&b.0));
+  for (id, (_, state)) in nodes.iter().enumerate() {
+    ids.insert(*state, id);
+  }
+
+  let mut merged: BTreeMap<(usize, usize), Vec<String>> = BTreeMap::new();
+  for (from, label, to) in edges {
+    merged
+      .entry((ids[from], ids[to]))
+      .or_default()
+      .push(escape(&label));
+  }
+
+  let mut dot = String::from("digraph {\n  rankdir=LR;\n");
+  if initial.is_some() {
+    dot.push_str("  __start [shape=point];\n");
+  }
+  for (id, (label, state)) in nodes.iter().enumerate() {
+    let shape = match accepting(state) {
+      true => "doublecircle",
+      false => "circle",
+    };
+    writeln!(dot, "  s{id} [label=\"{}\", shape={shape}];", escape(label)).unwrap();
+  }
+  if let Some(initial) = initial {
+    writeln!(dot, "  __start -> s{};", ids[initial]).unwrap();
+  }
+  for ((from, to), mut labels) in merged {
+    labels.sort();
+    writeln!(
+      dot,
+      "  s{from} -> s{to} [label=\"{}\"];",
+      labels.join("\\n")
+    )
+    .unwrap();
+  }
+  dot.push_str("}\n");
+  dot
+}
+
+/// DOT graph of a transition table as used by [`DriverTransitionTable`](crate::DriverTransitionTable)
+pub fn transition_table_to_dot<State, Input, Output>(
+  tt: &HashMap<(State, Input), (State, Output)>,
+) -> String
+where
+  State: Display + Hash + Eq,
+  Input: Display,
+  Output: Display,
+{
+  transition_table_to_dot_with(
+    tt,
+    None,
+    ToString::to_string,
+    ToString::to_string,
+    ToString::to_string,
+  )
+}
+
+/// DOT graph of a transition table with custom labels and an optional initial state
+pub fn transition_table_to_dot_with<State, Input, Output>(
+  tt: &HashMap<(State, Input), (State, Output)>,
+  initial: Option<&State>,
+  state: impl Fn(&State) -> String,
+  input: impl Fn(&Input) -> String,
+  output: impl Fn(&Output) -> String,
+) -> String
+where
+  State: Hash + Eq,
+{
+  render(
+    tt.iter().flat_map(|((from, _), (to, _))| [from, to]),
+    initial,
+    |_| false,
+    tt.iter()
+      .map(|((from, i), (to, o))| (from, format!("{} / {}", input(i), output(o)), to)),
+    state,
+  )
+}
+
+impl<State, Input, Output> Mealy<State, Input, Output>
+where
+  State: Hash + Eq,
+{
+  pub fn to_dot(&self) -> String
+  where
+    State: Display,
+    Input: Display,
+    Output: Display,
+  {
+    self.to_dot_with(
+      ToString::to_string,
+      ToString::to_string,
+      ToString::to_string,
+    )
+  }
+
+  pub fn to_dot_with(
+    &self,
+    state: impl Fn(&State) -> String,
+    input: impl Fn(&Input) -> String,
+    output: impl Fn(&Output) -> String,
+  ) -> String {
+    transition_table_to_dot_with(self.table(), Some(self.initial()), state, input, output)
+  }
+}
+
+impl<State, Symbol> Dfa<State, Symbol>
+where
+  State: Hash + Eq,
+{
+  pub fn to_dot(&self) -> String
+  where
+    State: Display,
+    Symbol: Display,
+  {
+    self.to_dot_with(ToString::to_string, ToString::to_string)
+  }
+
+  pub fn to_dot_with(
+    &self,
+    state: impl Fn(&State) -> String,
+    symbol: impl Fn(&Symbol) -> String,
+  ) -> String {
+    let transitions = self.transitions();
+    render(
+      transitions
+        .iter()
+        .flat_map(|((from, _), to)| [from, to])
+        .chain(self.accepting()),
+      Some(self.initial()),
+      |state| self.accepting().contains(state),
+      transitions
+        .iter()
+        .map(|((from, s), to)| (from, symbol(s), to)),
+      state,
+    )
+  }
+}
+
+impl<State, Symbol> Nfa<State, Symbol>
+where
+  State: Hash + Eq,
+{
+  /// Epsilon moves are labelled `ε`
+  pub fn to_dot(&self) -> String
+  where
+    State: Display,
+    Symbol: Display,
+  {
+    self.to_dot_with(ToString::to_string, ToString::to_string)
+  }
+
+  pub fn to_dot_with(
+    &self,
+    state: impl Fn(&State) -> String,
+    symbol: impl Fn(&Symbol) -> String,
+  ) -> String {
+    let symbols = self
+      .transitions()
+      .iter()
+      .flat_map(|((from, s), targets)| targets.iter().map(move |to| (from, s, to)));
+    let epsilon = self
+      .epsilon()
+      .iter()
+      .flat_map(|(from, targets)| targets.iter().map(move |to| (from, to)));
+    let states: Vec<&State> = symbols
+      .clone()
+      .flat_map(|(from, _, to)| [from, to])
+      .chain(epsilon.clone().flat_map(|(from, to)| [from, to]))
+      .chain(self.accepting())
+      .collect();
+    render(
+      states,
+      Some(self.initial()),
+      |state| self.accepting().contains(state),
+      symbols
+        .map(|(from, s, to)| (from, symbol(s), to))
+        .chain(epsilon.map(|(from, to)| (from, EPSILON.to_string(), to))),
+      state,
+    )
+  }
+}
+
+/// Invalid or unsupported DOT input
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DotError {
+  /// One-based line of the offending token
+  pub line: usize,
+  /// One-based column of the offending token, counted in characters
+  pub column: usize,
+  pub kind: DotErrorKind,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DotErrorKind {
+  UnexpectedEnd,
+  Unexpected(String),
+  UnterminatedString,
+  /// Undirected graphs and edges
+  Undirected,
+  /// Subgraphs, ports and HTML strings
+  Unsupported(String),
+  MissingLabel,
+  /// Edge label line without `/` between input and output
+  MissingOutput(String),
+  InvalidState(String),
+  InvalidInput(String),
+  InvalidOutput(String),
+  /// Second transition for the same state and input
+  Nondeterministic {
+    state: String,
+    input: String,
+  },
+  /// No or several edges from a `shape=point` start node
+  Initial,
+}
+
+impl Display for DotError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match &self.kind {
+      DotErrorKind::UnexpectedEnd => write!(f, "unexpected end of input")?,
+      DotErrorKind::Unexpected(token) => write!(f, "unexpected {token:?}")?,
+      DotErrorKind::UnterminatedString => write!(f, "unterminated string")?,
+      DotErrorKind::Undirected => write!(f, "undirected graphs are not supported")?,
+      DotErrorKind::Unsupported(token) => write!(f, "{token:?} is not supported")?,
+      DotErrorKind::MissingLabel => write!(f, "edge without label")?,
+      DotErrorKind::MissingOutput(label) => write!(f, "expected `input / output` in {label:?}")?,
+      DotErrorKind::InvalidState(label) => write!(f, "invalid state {label:?}")?,
+      DotErrorKind::InvalidInput(label) => write!(f, "invalid input {label:?}")?,
+      DotErrorKind::InvalidOutput(label) => write!(f, "invalid output {label:?}")?,
+      DotErrorKind::Nondeterministic { state, input } => {
+        write!(f, "second transition from {state:?} on {input:?}")?
+      }
+      DotErrorKind::Initial => write!(f, "expected exactly one edge from a start point")?,
+    }
+    write!(f, " at {}:{}", self.line, self.column)
+  }
+}
+impl Error for DotError {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Position {
+  line: usize,
+  column: usize,
+}
+impl Position {
+  fn error(self, kind: DotErrorKind) -> DotError {
+    DotError {
+      line: self.line,
+      column: self.column,
+      kind,
+    }
+  }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+  /// Identifier, numeral or quoted string, quoted strings keep their escapes
+  Id {
+    text: String,
+    quoted: bool,
+  },
+  Arrow,
+  Line,
+  Punctuation(char),
+}
+
+struct Lexer<'a> {
+  chars: Peekable<Chars<'a>>,
+  position: Position,
+}
+impl Lexer<'_> {
+  fn bump(&mut self) -> Option<char> {
+    let c = self.chars.next()?;
+    match c {
+      '\n' => {
+        self.position.line += 1;
+        self.position.column = 1;
+      }
+      _ => self.position.column += 1,
+    }
+    Some(c)
+  }
+
+  fn skip_trivia(&mut self) {
+    let mut line_start = self.position.column == 1;
+    while let Some(&c) = self.chars.peek() {
+      if c.is_whitespace() {
+        self.bump();
+      } else if c == '#' && line_start {
+        while self.chars.peek().is_some_and(|&c| c != '\n') {
+          self.bump();
+        }
+      } else if c == '/' {
+        let mut lookahead = self.chars.clone();
+        lookahead.next();
+        match lookahead.next() {
+          Some('/') => {
+            while self.chars.peek().is_some_and(|&c| c != '\n') {
+              self.bump();
+            }
+          }
+          Some('*') => {
+            self.bump();
+            self.bump();
+            let mut previous = ' ';
+            while let Some(c) = self.bump() {
+              if previous == '*' && c == '/' {
+                break;
+              }
+              previous = c;
+            }
+          }
+          _ => return,
+        }
+      } else {
+        return;
+      }
+      line_start = self.position.column == 1;
+    }
+  }
+
+  fn next_token(&mut self) -> Result<Option<(Position, Token)>, DotError> {
+    self.skip_trivia();
+    let position = self.position;
+    let Some(c) = self.bump() else {
+      return Ok(None);
+    };
+    let token = match c {
+      '"' => {
+        let mut text = String::new();
+        loop {
+          match self.bump() {
+            None => return Err(position.error(DotErrorKind::UnterminatedString)),
+            Some('"') => break,
+            Some('\\') => match self.bump() {
+              None => return Err(position.error(DotErrorKind::UnterminatedString)),
+              Some('"') => text.push('"'),
+              Some('\n') => {}
+              Some(c) => {
+                text.push('\\');
+                text.push(c);
+              }
+            },
+            Some(c) => text.push(c),
+          }
+        }
+        Token::Id { text, quoted: true }
+      }
+      '-' if self.chars.peek() == Some(&'>') => {
+        self.bump();
+        Token::Arrow
+      }
+      '-' if self.chars.peek() == Some(&'-') => {
+        self.bump();
+        Token::Line
+      }
+      '<' => return Err(position.error(DotErrorKind::Unsupported("<".into()))),
+      c if c.is_alphanumeric() || c == '_' || c == '.' || c == '-' => {
+        let mut text = String::from(c);
+        while let Some(&c) = self.chars.peek() {
+          if !(c.is_alphanumeric() || c == '_' || c == '.') {
+            break;
+          }
+          text.push(c);
+          self.bump();
+        }
+        Token::Id {
+          text,
+          quoted: false,
+        }
+      }
+      c => Token::Punctuation(c),
+    };
+    Ok(Some((position, token)))
+  }
+}
+
+type Attributes = HashMap<String, (String, Position)>;
+type Table<State, Input, Output> = HashMap<(State, Input), (State, Output)>;
+
+/// Nodes and edges of a parsed digraph
+#[derive(Default)]
+struct Graph {
+  nodes: HashMap<String, (Attributes, Position)>,
+  edges: Vec<(String, String, Attributes, Position)>,
+}
+impl Graph {
+  /// Declares a node on first use and merges `attributes` into it
+  fn node(&mut self, id: &str, position: Position, defaults: &Attributes, attributes: Attributes) {
+    let (node, _) = self
+      .nodes
+      .entry(id.to_string())
+      .or_insert_with(|| (defaults.clone(), position));
+    node.extend(attributes);
+  }
+}
+
+struct Parser<'a> {
+  lexer: Lexer<'a>,
+  peeked: Option<(Position, Token)>,
+}
+impl Parser<'_> {
+  fn peek(&mut self) -> Result<Option<&Token>, DotError> {
+    if self.peeked.is_none() {
+      self.peeked = self.lexer.next_token()?;
+    }
+    Ok(self.peeked.as_ref().map(|(_, token)| token))
+  }
+
+  fn next(&mut self) -> Result<(Position, Token), DotError> {
+    self.peek()?;
+    self
+      .peeked
+      .take()
+      .ok_or(self.lexer.position.error(DotErrorKind::UnexpectedEnd))
+  }
+
+  fn eat(&mut self, punctuation: char) -> Result<bool, DotError> {
+    let found = self.peek()? == Some(&Token::Punctuation(punctuation));
+    if found {
+      self.next()?;
+    }
+    Ok(found)
+  }
+
+  fn expect(&mut self, punctuation: char) -> Result<(), DotError> {
+    match self.next()? {
+      (_, Token::Punctuation(c)) if c == punctuation => Ok(()),
+      (position, token) => Err(position.error(DotErrorKind::Unexpected(describe(&token)))),
+    }
+  }
+
+  fn id(&mut self) -> Result<(Position, String), DotError> {
+    match self.next()? {
+      (position, Token::Id { text, .. }) => Ok((position, text)),
+      (position, token) => Err(position.error(DotErrorKind::Unexpected(describe(&token)))),
+    }
+  }
+
+  fn keyword(token: Option<&Token>, keyword: &str) -> bool {
+    matches!(token, Some(Token::Id { text, quoted: false }) if text.eq_ignore_ascii_case(keyword))
+  }
+
+  fn graph(&mut self) -> Result<Graph, DotError> {
+    if Self::keyword(self.peek()?, "strict") {
+      self.next()?;
+    }
+    let (position, kind) = self.id()?;
+    if kind.eq_ignore_ascii_case("graph") {
+      return Err(position.error(DotErrorKind::Undirected));
+    }
+    if !kind.eq_ignore_ascii_case("digraph") {
+      return Err(position.error(DotErrorKind::Unexpected(kind)));
+    }
+    if !self.eat('{')? {
+      self.id()?;
+      self.expect('{')?;
+    }
+
+    let mut graph = Graph::default();
+    let mut node_defaults = Attributes::new();
+    let mut edge_defaults = Attributes::new();
+    while !self.eat('}')? {
+      let (position, token) = self.next()?;
+      let id = match token {
+        Token::Id {
+          text,
+          quoted: false,
+        } if text.eq_ignore_ascii_case("subgraph") => {
+          return Err(position.error(DotErrorKind::Unsupported(text)));
+        }
+        Token::Punctuation('{') => {
+          return Err(position.error(DotErrorKind::Unsupported("{".into())));
+        }
+        Token::Id { text, quoted } => (text, quoted),
+        token => return Err(position.error(DotErrorKind::Unexpected(describe(&token)))),
+      };
+
+      let is_keyword = |keyword: &str| !id.1 && id.0.eq_ignore_ascii_case(keyword);
+      if is_keyword("graph") {
+        self.attributes()?;
+      } else if is_keyword("node") {
+        node_defaults.extend(self.attributes()?);
+      } else if is_keyword("edge") {
+        edge_defaults.extend(self.attributes()?);
+      } else if self.eat('=')? {
+        self.id()?;
+      } else if self.peek()? == Some(&Token::Arrow) {
+        let mut chain = vec![(position, id.0)];
+        while self.peek()? == Some(&Token::Arrow) {
+          self.next()?;
+          chain.push(self.id()?);
+        }
+        let mut attributes = edge_defaults.clone();
+        attributes.extend(self.attributes()?);
+        for (position, node) in &chain {
+          graph.node(node, *position, &node_defaults, Attributes::new());
+        }
+        for pair in chain.windows(2) {
+          let ((position, from), (_, to)) = (&pair[0], &pair[1]);
+          graph
+            .edges
+            .push((from.clone(), to.clone(), attributes.clone(), *position));
+        }
+      } else if self.peek()? == Some(&Token::Line) {
+        let (position, _) = self.next()?;
+        return Err(position.error(DotErrorKind::Undirected));
+      } else if self.eat(':')? {
+        return Err(position.error(DotErrorKind::Unsupported(":".into())));
+      } else {
+        let attributes = self.attributes()?;
+        graph.node(&id.0, position, &node_defaults, attributes);
+      }
+      while self.eat(';')? || self.eat(',')? {}
+    }
+    match self.lexer.next_token()? {
+      None => Ok(graph),
+      Some((position, token)) => Err(position.error(DotErrorKind::Unexpected(describe(&token)))),
+    }
+  }
+
+  /// Optional attribute lists `[a=b, c=d][e=f]`
+  fn attributes(&mut self) -> Result<Attributes, DotError> {
+    let mut attributes = Attributes::new();
+    while self.eat('[')? {
+      while !self.eat(']')? {
+        let (_, key) = self.id()?;
+        self.expect('=')?;
+        let (position, value) = self.id()?;
+        attributes.insert(key, (value, position));
+        while self.eat(',')? || self.eat(';')? {}
+      }
+    }
+    Ok(attributes)
+  }
+}
+
+fn describe(token: &Token) -> String {
+  match token {
+    Token::Id { text, .. } => text.clone(),
+    Token::Arrow => "->".into(),
+    Token::Line => "--".into(),
+    Token::Punctuation(c) => c.to_string(),
+  }
+}
+
+/// Lines of a label, resolving the `\n`, `\l`, `\r` breaks and `\\` escapes
+fn label_lines(label: &str) -> Vec<String> {
+  let mut lines = vec![String::new()];
+  let mut chars = label.chars();
+  while let Some(c) = chars.next() {
+    let line = lines.last_mut().unwrap();
+    match (c, chars.clone().next()) {
+      ('\\', Some('n' | 'l' | 'r')) => {
+        chars.next();
+        lines.push(String::new());
+      }
+      ('\\', Some(escaped)) => {
+        chars.next();
+        line.push(escaped);
+      }
+      (c, _) => line.push(c),
+    }
+  }
+  if lines.len() > 1 && lines.last().unwrap().is_empty() {
+    lines.pop();
+  }
+  lines
+}
+
+/// Transition table from a DOT digraph
+///
+/// States are parsed from the node `label`, or the node id without a label.
+/// Every edge label line is parsed as `input / output`, split at the first `/`.
+/// Nodes with `shape=point` only mark the initial state and are skipped.
+pub fn transition_table_from_dot<State, Input, Output>(
+  dot: &str,
+) -> Result<Table<State, Input, Output>, DotError>
+where
+  State: FromStr + Hash + Eq + Clone,
+  Input: FromStr + Hash + Eq,
+  Output: FromStr,
+{
+  parse_dot(dot).map(|parsed| parsed.table)
+}
+
+/// Start point targets and transition table of a DOT digraph
+struct Parsed<State, Input, Output> {
+  initial: Vec<(State, Position)>,
+  table: Table<State, Input, Output>,
+}
+
+fn parse_dot<State, Input, Output>(dot: &str) -> Result<Parsed<State, Input, Output>, DotError>
+where
+  State: FromStr + Hash + Eq + Clone,
+  Input: FromStr + Hash + Eq,
+  Output: FromStr,
+{
+  let mut parser = Parser {
+    lexer: Lexer {
+      chars: dot.chars().peekable(),
+      position: Position { line: 1, column: 1 },
+    },
+    peeked: None,
+  };
+  let graph = parser.graph()?;
+
+  let is_start = |id: &str| {
+    graph.nodes[id]
+      .0
+      .get("shape")
+      .is_some_and(|(shape, _)| shape == "point")
+  };
+  let label = |id: &str| {
+    let (attributes, position) = &graph.nodes[id];
+    match attributes.get("label") {
+      Some((label, position)) => (label_lines(label).join("\n"), *position),
+      None => (id.to_string(), *position),
+    }
+  };
+  let state = |id: &str| {
+    let (label, position) = label(id);
+    label
+      .trim()
+      .parse::<State>()
+      .map_err(|_| position.error(DotErrorKind::InvalidState(label)))
+  };
+
+  let mut initial = Vec::new();
+  let mut table = HashMap::new();
+  for (from, to, attributes, position) in &graph.edges {
+    if is_start(from) {
+      initial.push((state(to)?, *position));
+      continue;
+    }
+    let (from_label, from, to) = (label(from).0, state(from)?, state(to)?);
+    let Some((label, position)) = attributes.get("label") else {
+      return Err(position.error(DotErrorKind::MissingLabel));
+    };
+    for line in label_lines(label) {
+      let Some((input, output)) = line.split_once('/') else {
+        return Err(position.error(DotErrorKind::MissingOutput(line)));
+      };
+      let (input_text, output_text) = (input.trim(), output.trim());
+      let input = input_text
+        .parse::<Input>()
+        .map_err(|_| position.error(DotErrorKind::InvalidInput(input_text.into())))?;
+      let output = output_text
+        .parse::<Output>()
+        .map_err(|_| position.error(DotErrorKind::InvalidOutput(output_text.into())))?;
+      match table.entry((from.clone(), input)) {
+        Entry::Occupied(_) => {
+          return Err(position.error(DotErrorKind::Nondeterministic {
+            state: from_label,
+            input: input_text.into(),
+          }));
+        }
+        Entry::Vacant(entry) => {
+          entry.insert((to.clone(), output));
+        }
+      }
+    }
+  }
+  Ok(Parsed { initial, table })
+}
+
+impl<State, Input, Output> Mealy<State, Input, Output>
+where
+  State: FromStr + Hash + Eq + Clone,
+  Input: FromStr + Hash + Eq,
+  Output: FromStr,
+{
+  /// Mealy machine from a DOT digraph with a single edge from a `shape=point` node
+  ///
+  /// See [`transition_table_from_dot`] for the expected labels.
+  pub fn from_dot(dot: &str) -> Result<Self, DotError> {
+    let Parsed { mut initial, table } = parse_dot(dot)?;
+    match initial.len() {
+      1 => Ok(Mealy::new(initial.pop().unwrap().0, table)),
+      0 => Err(Position { line: 1, column: 1 }.error(DotErrorKind::Initial)),
+      _ => Err(initial[1].1.error(DotErrorKind::Initial)),
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use std::{collections::HashSet, fmt};
+
+  #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+  enum State {
+    Locked,
+    Unlocked,
+  }
+  impl fmt::Display for State {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+      fmt::Debug::fmt(self, f)
+    }
+  }
+
+  #[test]
+  fn turnstile_dot() {
+    let transition_table = HashMap::from([
+      ((State::Locked, "push"), (State::Locked, "blocked")),
+      ((State::Locked, "coin"), (State::Unlocked, "unlock")),
+      ((State::Unlocked, "coin"), (State::Unlocked, "refund")),
+      ((State::Unlocked, "push"), (State::Locked, "lock")),
+    ]);
+    assert_eq!(
+      Mealy::new(State::Locked, transition_table.clone()).to_dot(),
+      r#"digraph {
+  rankdir=LR;
+  __start [shape=point];
+  s0 [label="Locked", shape=circle];
+  s1 [label="Unlocked", shape=circle];
+  __start -> s0;
+  s0 -> s0 [label="push / blocked"];
+  s0 -> s1 [label="coin / unlock"];
+  s1 -> s0 [label="push / lock"];
+  s1 -> s1 [label="coin / refund"];
+}
+"#
+    );
+
+    let dot = transition_table_to_dot_with(
+      &transition_table,
+      None,
+      |state| format!("{state:?}").to_lowercase(),
+      |input| input.to_uppercase(),
+      |output| format!("\"{output}\""),
+    );
+    assert!(!dot.contains("__start"));
+    assert!(dot.contains(r#"s0 [label="locked", shape=circle];"#));
+    assert!(dot.contains(r#"s0 -> s1 [label="COIN / \"unlock\""];"#));
+  }
+
+  #[test]
+  fn acceptor_dot() {
+    let dfa = Dfa::new(
+      0,
+      HashMap::from([((0, 'a'), 1), ((0, 'b'), 1), ((1, 'a'), 1)]),
+      HashSet::from([1]),
+    );
+    let dot = dfa.to_dot();
+    assert!(dot.contains("s1 [label=\"1\", shape=doublecircle];"));
+    assert!(dot.contains("s0 -> s1 [label=\"a\\nb\"];"));
+    assert!(dot.contains("__start -> s0;"));
+
+    let nfa = Nfa::new(
+      0,
+      HashMap::from([((1, 'a'), HashSet::from([2]))]),
+      HashMap::from([(0, HashSet::from([1, 2]))]),
+      HashSet::from([2]),
+    );
+    let dot = nfa.to_dot_with(|state| format!("q{state}"), |symbol| symbol.to_string());
+    assert!(dot.contains("s0 [label=\"q0\", shape=circle];"));
+    assert!(dot.contains("s0 -> s1 [label=\"ε\"];"));
+    assert!(dot.contains("s1 -> s2 [label=\"a\"];"));
+  }
+
+  #[test]
+  fn import_round_trip() {
+    let mealy = Mealy::new(
+      0u8,
+      HashMap::from([
+        ((0, 'a'), (1, 10u16)),
+        ((0, 'b'), (1, 11)),
+        ((1, 'a'), (0, 12)),
+        ((1, 'b'), (1, 13)),
+      ]),
+    );
+    let imported = Mealy::<u8, char, u16>::from_dot(&mealy.to_dot()).unwrap();
+    assert_eq!(imported.initial(), mealy.initial());
+    assert_eq!(imported.table(), mealy.table());
+
+    let dot = r#"
+      /* hand written */
+      digraph turnstile {
+        node [shape=circle]
+        start [shape=point]
+        start -> Locked
+        Locked -> Locked [label="push / blocked"] // self loop
+        Locked -> Unlocked -> Locked [label="coin / ok"]
+        Unlocked -> Unlocked [label="push / stay\lkick / stay\l"]
+      }
+    "#;
+    let table = transition_table_from_dot::<String, String, String>(dot).unwrap();
+    assert_eq!(table.len(), 5);
+    assert_eq!(
+      table[&("Unlocked".into(), "kick".into())],
+      ("Unlocked".into(), "stay".into())
+    );
+    assert_eq!(
+      Mealy::<String, String, String>::from_dot(dot)
+        .unwrap()
+        .initial(),
+      "Locked"
+    );
+  }
+
+  #[test]
+  fn import_errors() {
+    let error = |dot: &str| transition_table_from_dot::<u8, char, u8>(dot).unwrap_err();
+    assert_eq!(error("graph { 0 -- 1 }").kind, DotErrorKind::Undirected);
+    assert_eq!(
+      error("digraph {\n  0 -> 1 [label=\"a / x\"]\n}").to_string(),
+      "invalid output \"x\" at 2:17"
+    );
+    assert_eq!(error("digraph { 0 -> 1 }").kind, DotErrorKind::MissingLabel);
+    assert_eq!(
+      error("digraph { 0 -> 1 [label=\"a\"] }").kind,
+      DotErrorKind::MissingOutput("a".into())
+    );
+    assert_eq!(
+      error("digraph { 0 -> 1 [label=\"a / 1\\na / 2\"] }").kind,
+      DotErrorKind::Nondeterministic {
+        state: "0".into(),
+        input: "a".into()
+      }
+    );
+    assert_eq!(
+      error("digraph { subgraph { 0 } }").kind,
+      DotErrorKind::Unsupported("subgraph".into())
+    );
+    let DotError { line, column, kind } = error("digraph {\n  0 -> ;\n}");
+    assert_eq!(
+      (line, column, kind),
+      (2, 8, DotErrorKind::Unexpected(";".into()))
+    );
+    assert_eq!(error("digraph {").kind, DotErrorKind::UnexpectedEnd);
+    assert_eq!(
+      Mealy::<u8, char, u8>::from_dot("digraph { 0 -> 1 [label=\"a / 1\"] }")
+        .unwrap_err()
+        .kind,
+      DotErrorKind::Initial
+    );
+  }
+}
@@ -0,0 +1,266 @@
+use crate::{Dfa, Mealy};
+use std::{
+  collections::{hash_map::Entry, HashMap, HashSet, VecDeque},
+  hash::Hash,
+};
+
+/// Behavioural comparison of automata
+///
+/// Failures carry a shortest distinguishing input word as counterexample.
+pub trait Equivalence<Rhs = Self> {
+  type Symbol;
+
+  /// Same language, or same output for every input sequence
+  fn equivalent(&self, other: &Rhs) -> Result<(), Vec<Self::Symbol>>;
+
+  /// Language inclusion, or `other` extends the translation of `self`
+  fn is_subset(&self, other: &Rhs) -> Result<(), Vec<Self::Symbol>>;
+}
+
+pub fn equivalent<A, B>(a: &A, b: &B) -> Result<(), Vec<A::Symbol>>
+where
+  A: Equivalence<B>,
+{
+  a.equivalent(b)
+}
+
+pub fn is_subset<A, B>(a: &A, b: &B) -> Result<(), Vec<A::Symbol>>
+where
+  A: Equivalence<B>,
+{
+  a.is_subset(b)
+}
+
+enum Edge<Pair> {
+  Next(Pair),
+  Witness,
+  Stop,
+}
+
+/// Breadth-first search of the product automaton for a shortest witness
+fn search<Pair, Symbol>(
+  start: Pair,
+  alphabet: &[Symbol],
+  witness: impl Fn(&Pair) -> bool,
+  step: impl Fn(&Pair, &Symbol) -> Edge<Pair>,
+) -> Result<(), Vec<Symbol>>
+where
+  Pair: Copy + Hash + Eq,
+  Symbol: Clone,
+{
+  let mut parents: HashMap<Pair, Option<(Pair, usize)>> = HashMap::from([(start, None)]);
+  let word =
+    |parents: &HashMap<Pair, Option<(Pair, usize)>>, mut pair: Pair, last: Option<usize>| {
+      let mut word: Vec<Symbol> = last
+        .map(|symbol| alphabet[symbol].clone())
+        .into_iter()
+        .collect();
+      while let Some((parent, symbol)) = parents[&pair] {
+        word.push(alphabet[symbol].clone());
+        pair = parent;
+      }
+      word.reverse();
+      word
+    };
+
+  let mut queue = VecDeque::from([start]);
+  while let Some(pair) = queue.pop_front() {
+    if witness(&pair) {
+      return Err(word(&parents, pair, None));
+    }
+    for (index, symbol) in alphabet.iter().enumerate() {
+      match step(&pair, symbol) {
+        Edge::Next(next) => {
+          if let Entry::Vacant(entry) = parents.entry(next) {
+            entry.insert(Some((pair, index)));
+            queue.push_back(next);
+          }
+        }
+        Edge::Witness => return Err(word(&parents, pair, Some(index))),
+        Edge::Stop => {}
+      }
+    }
+  }
+  Ok(())
+}
+
+fn dfa_alphabet<StateA, StateB, Symbol>(
+  a: &Dfa<StateA, Symbol>,
+  b: &Dfa<StateB, Symbol>,
+) -> Vec<Symbol>
+where
+  Symbol: Clone + Hash + Eq,
+{
+  let a = a.transitions().keys().map(|(_, symbol)| symbol);
+  let b = b.transitions().keys().map(|(_, symbol)| symbol);
+  a.chain(b)
+    .collect::<HashSet<_>>()
+    .into_iter()
+    .cloned()
+    .collect()
+}
+
+impl<StateA, StateB, Symbol> Equivalence<Dfa<StateB, Symbol>> for Dfa<StateA, Symbol>
+where
+  StateA: Copy + Hash + Eq,
+  StateB: Copy + Hash + Eq,
+  Symbol: Clone + Hash + Eq,
+{
+  type Symbol = Symbol;
+
+  fn equivalent(&self, other: &Dfa<StateB, Symbol>) -> Result<(), Vec<Symbol>> {
+    search(
+      (Some(*self.initial()), Some(*other.initial())),
+      &dfa_alphabet(self, other),
+      |&(a, b)| {
+        a.is_some_and(|a| self.is_accepting(&a)) != b.is_some_and(|b| other.is_accepting(&b))
+      },
+      |&(a, b), symbol| match (
+        a.and_then(|a| self.run_from(a, [symbol.clone()])),
+        b.and_then(|b| other.run_from(b, [symbol.clone()])),
+      ) {
+        (None, None) => Edge::Stop,
+        next => Edge::Next(next),
+      },
+    )
+  }
+
+  fn is_subset(&self, other: &Dfa<StateB, Symbol>) -> Result<(), Vec<Symbol>> {
+    search(
+      (*self.initial(), Some(*other.initial())),
+      &dfa_alphabet(self, other),
+      |&(a, b)| self.is_accepting(&a) && !b.is_some_and(|b| other.is_accepting(&b)),
+      |&(a, b), symbol| match self.run_from(a, [symbol.clone()]) {
+        Some(a) => Edge::Next((a, b.and_then(|b| other.run_from(b, [symbol.clone()])))),
+        None => Edge::Stop,
+      },
+    )
+  }
+}
+
+fn mealy_alphabet<StateA, StateB, Input, Output>(
+  a: &Mealy<StateA, Input, Output>,
+  b: &Mealy<StateB, Input, Output>,
+) -> Vec<Input>
+where
+  Input: Clone + Hash + Eq,
+{
+  let a = a.table().keys().map(|(_, input)| input);
+  let b = b.table().keys().map(|(_, input)| input);
+  a.chain(b)
+    .collect::<HashSet<_>>()
+    .into_iter()
+    .cloned()
+    .collect()
+}
+
+impl<StateA, StateB, Input, Output> Equivalence<Mealy<StateB, Input, Output>>
+  for Mealy<StateA, Input, Output>
+where
+  StateA: Copy + Hash + Eq,
+  StateB: Copy + Hash + Eq,
+  Input: Clone + Hash + Eq,
+  Output: PartialEq,
+{
+  type Symbol = Input;
+
+  fn equivalent(&self, other: &Mealy<StateB, Input, Output>) -> Result<(), Vec<Input>> {
+    search(
+      (*self.initial(), *other.initial()),
+      &mealy_alphabet(self, other),
+      |_| false,
+      |&(a, b), input| match (
+        self.table().get(&(a, input.clone())),
+        other.table().get(&(b, input.clone())),
+      ) {
+        (None, None) => Edge::Stop,
+        (Some((a, x)), Some((b, y))) if x == y => Edge::Next((*a, *b)),
+        _ => Edge::Witness,
+      },
+    )
+  }
+
+  fn is_subset(&self, other: &Mealy<StateB, Input, Output>) -> Result<(), Vec<Input>> {
+    search(
+      (*self.initial(), *other.initial()),
+      &mealy_alphabet(self, other),
+      |_| false,
+      |&(a, b), input| match (
+        self.table().get(&(a, input.clone())),
+        other.table().get(&(b, input.clone())),
+      ) {
+        (None, _) => Edge::Stop,
+        (Some((a, x)), Some((b, y))) if x == y => Edge::Next((*a, *b)),
+        _ => Edge::Witness,
+      },
+    )
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn dfa(accepting: &[u8], transitions: &[(u8, char, u8)]) -> Dfa<u8, char> {
+    let transitions = transitions
+      .iter()
+      .map(|&(from, symbol, to)| ((from, symbol), to))
+      .collect();
+    Dfa::new(0, transitions, accepting.iter().copied().collect())
+  }
+
+  #[test]
+  fn dfa_counterexamples() {
+    // even number of a's, with and without redundant states
+    let even = dfa(&[0], &[(0, 'a', 1), (1, 'a', 0), (0, 'b', 0), (1, 'b', 1)]);
+    let redundant = dfa(
+      &[0, 2],
+      &[
+        (0, 'a', 1),
+        (1, 'a', 2),
+        (2, 'a', 1),
+        (0, 'b', 0),
+        (1, 'b', 1),
+        (2, 'b', 2),
+      ],
+    );
+    assert_eq!(equivalent(&even, &redundant), Ok(()));
+    assert_eq!(equivalent(&even, &even.minimize().0), Ok(()));
+
+    // a's only
+    let only_a = dfa(&[0, 1], &[(0, 'a', 1), (1, 'a', 0)]);
+    assert_eq!(is_subset(&only_a, &even), Err(vec!['a']));
+    assert_eq!(equivalent(&only_a, &even).unwrap_err().len(), 1);
+    let even_only_a = dfa(&[0], &[(0, 'a', 1), (1, 'a', 0)]);
+    assert_eq!(is_subset(&even_only_a, &even), Ok(()));
+    assert_eq!(is_subset(&even, &even_only_a), Err(vec!['b']));
+  }
+
+  #[test]
+  fn mealy_counterexamples() {
+    let toggle = Mealy::new(
+      false,
+      HashMap::from([((false, ()), (true, 1)), ((true, ()), (false, 0))]),
+    );
+    let counter = Mealy::new(
+      0u8,
+      HashMap::from([
+        ((0, ()), (1, 1)),
+        ((1, ()), (2, 0)),
+        ((2, ()), (3, 1)),
+        ((3, ()), (0, 0)),
+      ]),
+    );
+    assert_eq!(equivalent(&toggle, &counter), Ok(()));
+
+    let truncated = Mealy::new(
+      0u8,
+      HashMap::from([((0, ()), (1, 1)), ((1, ()), (2, 0)), ((2, ()), (3, 0))]),
+    );
+    assert_eq!(equivalent(&truncated, &toggle), Err(vec![(), (), ()]));
+
+    let prefix = Mealy::new(0u8, HashMap::from([((0, ()), (1, 1)), ((1, ()), (2, 0))]));
+    assert_eq!(is_subset(&prefix, &toggle), Ok(()));
+    assert_eq!(is_subset(&toggle, &prefix), Err(vec![(), (), ()]));
+  }
+}
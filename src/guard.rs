@@ -0,0 +1,381 @@
+use crate::{Driver, StateMachine, StepError, TryDriver};
+use std::{collections::HashMap, error::Error, fmt, hash::Hash};
+
+/// Discriminant of an input, e.g. `Coin` for `Coin(amount)`
+///
+/// Guarded transitions are keyed by the kind, the guards inspect the whole input.
+pub trait InputKind {
+  type Kind: Hash + Eq;
+  fn kind(&self) -> Self::Kind;
+}
+
+/// Predicate over the extended state and the input
+pub type Guard<Context, Input> = Box<dyn Fn(&Context, &Input) -> bool>;
+
+struct Candidate<State, Input, Output, Context> {
+  guard: Option<Guard<Context, Input>>,
+  to: State,
+  output: Output,
+}
+
+/// Transition table with guarded transitions
+///
+/// Every `(State, Kind)` pair has a list of candidate transitions, tried in the order they were added.
+/// The first candidate whose guard holds is taken, unguarded candidates always hold.
+/// An unguarded candidate after guarded ones thus is the default when no guard holds.
+pub struct GuardedTable<State, Input: InputKind, Output, Context = ()> {
+  #[allow(clippy::type_complexity)]
+  candidates: HashMap<(State, Input::Kind), Vec<Candidate<State, Input, Output, Context>>>,
+}
+impl<State, Input: InputKind, Output, Context> Default
+  for GuardedTable<State, Input, Output, Context>
+{
+  fn default() -> Self {
+    Self {
+      candidates: HashMap::new(),
+    }
+  }
+}
+impl<State, Input, Output, Context> GuardedTable<State, Input, Output, Context>
+where
+  State: Hash + Eq,
+  Input: InputKind,
+{
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Adds an unguarded candidate with the lowest priority so far
+  pub fn add(&mut self, from: State, kind: Input::Kind, to: State, output: Output) {
+    self.push(from, kind, None, to, output);
+  }
+
+  /// Adds a guarded candidate with the lowest priority so far
+  pub fn add_guarded(
+    &mut self,
+    from: State,
+    kind: Input::Kind,
+    guard: impl Fn(&Context, &Input) -> bool + 'static,
+    to: State,
+    output: Output,
+  ) {
+    self.push(from, kind, Some(Box::new(guard)), to, output);
+  }
+
+  fn push(
+    &mut self,
+    from: State,
+    kind: Input::Kind,
+    guard: Option<Guard<Context, Input>>,
+    to: State,
+    output: Output,
+  ) {
+    self
+      .candidates
+      .entry((from, kind))
+      .or_default()
+      .push(Candidate { guard, to, output });
+  }
+
+  /// Enabled candidates in priority order, as `(to, output)` pairs
+  pub fn enabled<'a>(
+    &'a self,
+    state: State,
+    context: &'a Context,
+    input: &'a Input,
+  ) -> impl Iterator<Item = (&'a State, &'a Output)> + 'a {
+    self
+      .enabled_candidates(state, context, input)
+      .map(|candidate| (&candidate.to, &candidate.output))
+  }
+
+  fn enabled_candidates<'a>(
+    &'a self,
+    state: State,
+    context: &'a Context,
+    input: &'a Input,
+  ) -> impl Iterator<Item = &'a Candidate<State, Input, Output, Context>> + 'a {
+    self
+      .candidates
+      .get(&(state, input.kind()))
+      .into_iter()
+      .flatten()
+      .filter(|candidate| {
+        candidate
+          .guard
+          .as_ref()
+          .is_none_or(|guard| guard(context, input))
+      })
+  }
+
+  /// Transition function for [`DriverTransitionFunction`](crate::DriverTransitionFunction)
+  ///
+  /// Returns `None` if no guard holds, so the driver's `try_step` reports a missing transition.
+  pub fn transition_function<'a>(
+    &'a self,
+    context: &'a Context,
+  ) -> impl Fn(State, Input) -> Option<(State, Output)> + 'a
+  where
+    State: Copy,
+    Output: Copy,
+  {
+    move |state, input| {
+      let (to, output) = self.enabled(state, context, &input).next()?;
+      Some((*to, *output))
+    }
+  }
+}
+
+/// Failure of a guarded step checked for overlapping guards
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GuardError<State, Input> {
+  /// No guard holds
+  Missing(StepError<State, Input>),
+  /// Several guards hold, with the targets of the guarded candidates that hold in priority order
+  Overlap {
+    state: State,
+    input: Input,
+    targets: Vec<State>,
+  },
+}
+impl<State: fmt::Debug, Input: fmt::Debug> fmt::Display for GuardError<State, Input> {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      GuardError::Missing(error) => error.fmt(f),
+      GuardError::Overlap {
+        state,
+        input,
+        targets,
+      } => write!(
+        f,
+        "overlapping guards from state {state:?} on input {input:?} to {targets:?}"
+      ),
+    }
+  }
+}
+impl<State: fmt::Debug, Input: fmt::Debug> Error for GuardError<State, Input> {}
+
+/// State machine driver with guarded transition table
+///
+/// Guards read the context, the driver never changes it.
+///
+/// Zero-cost construction
+pub struct DriverGuardedTable<'a, State, Input: InputKind, Output, Context = ()> {
+  sm: &'a mut StateMachine<State>,
+  table: &'a GuardedTable<State, Input, Output, Context>,
+  context: &'a Context,
+}
+impl<'a, State, Input: InputKind, Output, Context>
+  DriverGuardedTable<'a, State, Input, Output, Context>
+{
+  pub fn new(
+    sm: &'a mut StateMachine<State>,
+    table: &'a GuardedTable<State, Input, Output, Context>,
+    context: &'a Context,
+  ) -> Self {
+    Self { sm, table, context }
+  }
+}
+
+impl<'a, State, Input, Output, Context> DriverGuardedTable<'a, State, Input, Output, Context>
+where
+  State: Copy + Hash + Eq,
+  Input: InputKind,
+  Output: Copy,
+{
+  /// Like [`TryDriver::try_step`], but fails if more than one guard holds
+  ///
+  /// Evaluates every guard of the candidates instead of stopping at the first that holds.
+  /// Unguarded candidates never overlap, they are defaults or shadow the candidates after them.
+  pub fn try_step_checked(&mut self, input: Input) -> Result<Output, GuardError<State, Input>> {
+    let state = self.sm.state;
+    let enabled: Vec<_> = self
+      .table
+      .enabled_candidates(state, self.context, &input)
+      .collect();
+    // candidates after the first unguarded one are shadowed
+    let guarded: Vec<State> = enabled
+      .iter()
+      .take_while(|candidate| candidate.guard.is_some())
+      .map(|candidate| candidate.to)
+      .collect();
+    match enabled.first() {
+      None => Err(GuardError::Missing(StepError { state, input })),
+      Some(_) if guarded.len() > 1 => Err(GuardError::Overlap {
+        state,
+        input,
+        targets: guarded,
+      }),
+      Some(candidate) => {
+        self.sm.state = candidate.to;
+        Ok(candidate.output)
+      }
+    }
+  }
+}
+
+impl<'a, State, Input, Output, Context> Driver<Input, Output>
+  for DriverGuardedTable<'a, State, Input, Output, Context>
+where
+  State: Copy + Hash + Eq,
+  Input: InputKind,
+  Output: Copy,
+{
+  fn step(&mut self, input: Input) -> Output {
+    let (to, output) = self
+      .table
+      .enabled(self.sm.state, self.context, &input)
+      .next()
+      .unwrap();
+    self.sm.state = *to;
+    *output
+  }
+}
+
+impl<'a, State, Input, Output, Context> TryDriver<Input, Output>
+  for DriverGuardedTable<'a, State, Input, Output, Context>
+where
+  State: Copy + Hash + Eq,
+  Input: InputKind,
+  Output: Copy,
+{
+  type State = State;
+  fn try_step(&mut self, input: Input) -> Result<Output, StepError<State, Input>> {
+    let state = self.sm.state;
+    let enabled = self.table.enabled(state, self.context, &input).next();
+    match enabled.map(|(to, output)| (*to, *output)) {
+      Some((to, output)) => {
+        self.sm.state = to;
+        Ok(output)
+      }
+      None => Err(StepError { state, input }),
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{DriverExt, DriverTransitionFunction};
+
+  #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+  enum State {
+    Locked,
+    Unlocked,
+  }
+
+  #[derive(Debug, Copy, Clone, PartialEq, Eq)]
+  enum Input {
+    Coin(u32),
+    Push,
+  }
+  #[derive(Debug, PartialEq, Eq, Hash)]
+  enum Kind {
+    Coin,
+    Push,
+  }
+  impl InputKind for Input {
+    type Kind = Kind;
+    fn kind(&self) -> Kind {
+      match self {
+        Input::Coin(_) => Kind::Coin,
+        Input::Push => Kind::Push,
+      }
+    }
+  }
+
+  struct Context {
+    price: u32,
+  }
+
+  fn table() -> GuardedTable<State, Input, &'static str, Context> {
+    let coin = |input: &Input| match input {
+      Input::Coin(amount) => *amount,
+      Input::Push => 0,
+    };
+    let mut table = GuardedTable::new();
+    table.add_guarded(
+      State::Locked,
+      Kind::Coin,
+      move |context: &Context, input: &Input| coin(input) >= context.price,
+      State::Unlocked,
+      "unlock",
+    );
+    table.add_guarded(
+      State::Locked,
+      Kind::Coin,
+      move |context: &Context, input: &Input| coin(input) >= 10 * context.price,
+      State::Locked,
+      "jammed",
+    );
+    table.add(State::Locked, Kind::Coin, State::Locked, "refund");
+    table.add(State::Locked, Kind::Push, State::Locked, "blocked");
+    table.add_guarded(
+      State::Unlocked,
+      Kind::Coin,
+      move |context: &Context, input: &Input| coin(input) > context.price,
+      State::Unlocked,
+      "tip",
+    );
+    table.add(State::Unlocked, Kind::Push, State::Locked, "lock");
+    table
+  }
+
+  #[test]
+  fn priority_and_overlap() {
+    let mut table = table();
+    // shadowed by the unguarded lock
+    for output in ["stay", "hold"] {
+      let guard = |context: &Context, _: &Input| context.price > 0;
+      table.add_guarded(State::Unlocked, Kind::Push, guard, State::Unlocked, output);
+    }
+    let context = Context { price: 2 };
+    let mut state_machine = StateMachine::new(State::Locked);
+    let mut driver = DriverGuardedTable::new(&mut state_machine, &table, &context);
+    assert_eq!(
+      driver.run([Input::Coin(1), Input::Push, Input::Coin(2), Input::Push]),
+      vec!["refund", "blocked", "unlock", "lock"]
+    );
+    // the unguarded refund is the default, not an overlap
+    assert_eq!(driver.try_step_checked(Input::Coin(1)), Ok("refund"));
+    assert_eq!(
+      driver.try_step_checked(Input::Coin(20)),
+      Err(GuardError::Overlap {
+        state: State::Locked,
+        input: Input::Coin(20),
+        targets: vec![State::Unlocked, State::Locked]
+      })
+    );
+    assert_eq!(state_machine.state(), &State::Locked);
+    let mut driver = DriverGuardedTable::new(&mut state_machine, &table, &context);
+    assert_eq!(driver.try_step(Input::Coin(20)), Ok("unlock"));
+    assert_eq!(driver.try_step_checked(Input::Push), Ok("lock"));
+    assert_eq!(driver.try_step_checked(Input::Coin(3)), Ok("unlock"));
+    assert_eq!(
+      driver.try_step_checked(Input::Coin(1)),
+      Err(GuardError::Missing(StepError {
+        state: State::Unlocked,
+        input: Input::Coin(1)
+      }))
+    );
+    assert_eq!(state_machine.state(), &State::Unlocked);
+  }
+
+  #[test]
+  fn transition_function() {
+    let table = table();
+    let context = Context { price: 5 };
+    let tf = table.transition_function(&context);
+    let mut state_machine = StateMachine::new(State::Locked);
+    let mut driver = DriverTransitionFunction::new(&mut state_machine, &tf);
+    assert_eq!(driver.try_step(Input::Coin(5)), Ok("unlock"));
+    assert_eq!(driver.try_step(Input::Coin(6)), Ok("tip"));
+    assert_eq!(
+      driver.try_step(Input::Coin(5)),
+      Err(StepError {
+        state: State::Unlocked,
+        input: Input::Coin(5)
+      })
+    );
+  }
+}
@@ -1,4 +1,77 @@
 //! Various finite automaton
+//!
+//! The default `std` feature enables everything. Without it the crate is `no_std`, keeping the
+//! state machine, the function driver, [`Dense`] and [`ConstTable`]; the `alloc` feature adds
+#![cfg_attr(
+  feature = "alloc",
+  doc = "[`DriverExt::run`], [`DenseTable`] and [`DriverBTreeTable`]."
+)]
+#![cfg_attr(
+  not(feature = "alloc"),
+  doc = "`DriverExt::run`, `DenseTable` and `DriverBTreeTable`."
+)]
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
 
+#[cfg(feature = "alloc")]
+extern crate alloc;
+
+#[cfg(feature = "std")]
+pub mod alphabet;
+pub mod const_table;
+pub mod dense;
+#[cfg(feature = "std")]
+pub mod derivative;
+#[cfg(feature = "std")]
+pub mod dfa;
+#[cfg(feature = "std")]
+pub mod dot;
+#[cfg(feature = "std")]
+pub mod equivalence;
+#[cfg(feature = "std")]
+pub mod guard;
+#[cfg(feature = "std")]
+pub mod mealy;
+#[cfg(feature = "std")]
+pub mod moore;
+#[cfg(feature = "std")]
+pub mod nfa;
+#[cfg(feature = "std")]
+pub mod regex;
+#[cfg(feature = "std")]
+pub mod scxml;
+#[cfg(feature = "serde")]
+pub mod serde_table;
 pub mod sm;
+#[cfg(feature = "std")]
+pub mod statechart;
+
+#[cfg(feature = "std")]
+mod partition;
+
+#[cfg(feature = "std")]
+pub use alphabet::*;
+pub use const_table::*;
+pub use dense::*;
+#[cfg(feature = "std")]
+pub use derivative::*;
+#[cfg(feature = "std")]
+pub use dfa::*;
+#[cfg(feature = "std")]
+pub use dot::*;
+#[cfg(feature = "std")]
+pub use equivalence::*;
+#[cfg(feature = "std")]
+pub use guard::*;
+#[cfg(feature = "std")]
+pub use mealy::*;
+#[cfg(feature = "std")]
+pub use moore::*;
+#[cfg(feature = "std")]
+pub use nfa::*;
+#[cfg(feature = "std")]
+pub use regex::*;
+#[cfg(feature = "std")]
+pub use scxml::*;
 pub use sm::*;
+#[cfg(feature = "std")]
+pub use statechart::*;
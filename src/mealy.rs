@@ -0,0 +1,163 @@
+use crate::partition::hopcroft;
+use std::{collections::HashMap, hash::Hash};
+
+/// Mealy machine
+///
+/// Initial state together with a transition table for [`DriverTransitionTable`](crate::DriverTransitionTable).
+#[derive(Debug, Clone)]
+#[cfg_attr(
+  feature = "serde",
+  derive(serde::Serialize, serde::Deserialize),
+  serde(bound(
+    serialize = "State: serde::Serialize, Input: serde::Serialize, Output: serde::Serialize",
+    deserialize = "State: serde::Deserialize<'de> + Hash + Eq, Input: serde::Deserialize<'de> + Hash + Eq, Output: serde::Deserialize<'de>"
+  ))
+)]
+pub struct Mealy<State, Input, Output> {
+  initial: State,
+  #[cfg_attr(feature = "serde", serde(with = "crate::serde_table"))]
+  table: HashMap<(State, Input), (State, Output)>,
+}
+impl<State, Input, Output> Mealy<State, Input, Output> {
+  pub fn new(initial: State, table: HashMap<(State, Input), (State, Output)>) -> Self {
+    Self { initial, table }
+  }
+
+  pub fn initial(&self) -> &State {
+    &self.initial
+  }
+
+  pub fn table(&self) -> &HashMap<(State, Input), (State, Output)> {
+    &self.table
+  }
+}
+
+impl<State, Input, Output> Mealy<State, Input, Output>
+where
+  State: Copy + Hash + Eq,
+  Input: Clone + Hash + Eq,
+  Output: Clone + Hash + Eq,
+{
+  /// Minimal equivalent Mealy machine using Hopcroft's algorithm
+  ///
+  /// States are equivalent if they produce the same outputs for every input sequence.
+  /// Unreachable states are dropped, the returned mapping only contains the remaining states.
+  pub fn minimize(&self) -> (Mealy<usize, Input, Output>, HashMap<State, usize>) {
+    let mut outgoing: HashMap<State, Vec<_>> = HashMap::new();
+    for ((from, input), (to, output)) in &self.table {
+      outgoing.entry(*from).or_default().push((input, to, output));
+    }
+    let mut states = vec![self.initial];
+    let mut indices = HashMap::from([(self.initial, 0)]);
+    let mut alphabet = Vec::new();
+    let mut input_indices = HashMap::new();
+    let mut edges = Vec::new();
+    let mut next = 0;
+    while next < states.len() {
+      for &(input, to, output) in outgoing.get(&states[next]).into_iter().flatten() {
+        let input = *input_indices.entry(input).or_insert_with(|| {
+          alphabet.push(input.clone());
+          alphabet.len() - 1
+        });
+        let to = *indices.entry(*to).or_insert_with(|| {
+          states.push(*to);
+          states.len() - 1
+        });
+        edges.push((next, input, to, output));
+      }
+      next += 1;
+    }
+
+    // complete with a sink state, initially partitioned by outputs
+    let (n, k, sink) = (states.len() + 1, alphabet.len(), states.len());
+    let mut delta = vec![sink; n * k];
+    let mut signatures = vec![vec![None; k]; n];
+    for &(from, input, to, output) in &edges {
+      delta[from * k + input] = to;
+      signatures[from][input] = Some(output);
+    }
+    let mut signature_classes = HashMap::new();
+    let classes: Vec<usize> = signatures
+      .into_iter()
+      .map(|signature| {
+        let len = signature_classes.len();
+        *signature_classes.entry(signature).or_insert(len)
+      })
+      .collect();
+    let blocks = hopcroft(n, k, &delta, &classes);
+
+    let mut renumber = HashMap::new();
+    for &block in &blocks[..sink] {
+      let len = renumber.len();
+      renumber.entry(block).or_insert(len);
+    }
+    let table = edges
+      .iter()
+      .map(|&(from, input, to, output)| {
+        let key = (renumber[&blocks[from]], alphabet[input].clone());
+        (key, (renumber[&blocks[to]], output.clone()))
+      })
+      .collect();
+    let mapping = states
+      .iter()
+      .enumerate()
+      .map(|(index, state)| (*state, renumber[&blocks[index]]))
+      .collect();
+    (Mealy::new(0, table), mapping)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{DriverExt, DriverTransitionTable, StateMachine};
+
+  #[test]
+  fn turnstile_minimize() {
+    #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+    enum State {
+      Locked,
+      LockedAgain,
+      Unlocked,
+      Unreachable,
+    }
+    #[derive(Clone, PartialEq, Eq, Hash)]
+    enum Input {
+      Push,
+      Coin,
+    }
+    let transition_table = HashMap::from([
+      (
+        (State::Locked, Input::Push),
+        (State::LockedAgain, "blocked"),
+      ),
+      ((State::Locked, Input::Coin), (State::Unlocked, "unlock")),
+      (
+        (State::LockedAgain, Input::Push),
+        (State::Locked, "blocked"),
+      ),
+      (
+        (State::LockedAgain, Input::Coin),
+        (State::Unlocked, "unlock"),
+      ),
+      ((State::Unlocked, Input::Coin), (State::Unlocked, "refund")),
+      ((State::Unlocked, Input::Push), (State::LockedAgain, "lock")),
+      ((State::Unreachable, Input::Push), (State::Locked, "lock")),
+    ]);
+    let mealy = Mealy::new(State::Locked, transition_table);
+
+    let (minimal, mapping) = mealy.minimize();
+    assert_eq!(minimal.table().len(), 4);
+    assert_eq!(mapping.len(), 3);
+    assert_eq!(mapping[&State::Locked], mapping[&State::LockedAgain]);
+    assert_ne!(mapping[&State::Locked], mapping[&State::Unlocked]);
+
+    let inputs = [Input::Coin, Input::Push, Input::Push, Input::Coin];
+    let mut state_machine = StateMachine::new(*minimal.initial());
+    let mut driver = DriverTransitionTable::new(&mut state_machine, minimal.table());
+    assert_eq!(
+      driver.run(inputs),
+      vec!["unlock", "lock", "blocked", "unlock"]
+    );
+  }
+}
@@ -0,0 +1,314 @@
+use crate::{Driver, Mealy, StateMachine, StepError, TryDriver};
+use std::{collections::HashMap, error::Error, fmt, hash::Hash};
+
+/// Moore machine
+///
+/// Outputs depend on the state only, every state has an output.
+#[derive(Debug, Clone)]
+#[cfg_attr(
+  feature = "serde",
+  derive(serde::Serialize, serde::Deserialize),
+  serde(
+    try_from = "Unchecked<State, Input, Output>",
+    bound(
+      serialize = "State: serde::Serialize, Input: serde::Serialize, Output: serde::Serialize",
+      deserialize = "State: serde::Deserialize<'de> + fmt::Debug + Clone + Hash + Eq, Input: serde::Deserialize<'de> + Hash + Eq, Output: serde::Deserialize<'de>"
+    )
+  )
+)]
+pub struct MooreMachine<State, Input, Output> {
+  initial: State,
+  #[cfg_attr(feature = "serde", serde(with = "crate::serde_table::acceptor"))]
+  transitions: HashMap<(State, Input), State>,
+  #[cfg_attr(feature = "serde", serde(with = "crate::serde_table::state_output"))]
+  outputs: HashMap<State, Output>,
+}
+
+/// Fields of a deserialized [`MooreMachine`] before checking the outputs
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+#[serde(bound(
+  deserialize = "State: serde::Deserialize<'de> + Hash + Eq, Input: serde::Deserialize<'de> + Hash + Eq, Output: serde::Deserialize<'de>"
+))]
+struct Unchecked<State, Input, Output> {
+  initial: State,
+  #[serde(with = "crate::serde_table::acceptor")]
+  transitions: HashMap<(State, Input), State>,
+  #[serde(with = "crate::serde_table::state_output")]
+  outputs: HashMap<State, Output>,
+}
+#[cfg(feature = "serde")]
+impl<State, Input, Output> TryFrom<Unchecked<State, Input, Output>>
+  for MooreMachine<State, Input, Output>
+where
+  State: Clone + Hash + Eq,
+{
+  type Error = MissingOutput<State>;
+  fn try_from(unchecked: Unchecked<State, Input, Output>) -> Result<Self, Self::Error> {
+    Self::new(unchecked.initial, unchecked.transitions, unchecked.outputs)
+  }
+}
+
+/// State of a [`MooreMachine`] without output
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MissingOutput<State> {
+  pub state: State,
+}
+impl<State: fmt::Debug> fmt::Display for MissingOutput<State> {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "no output for state {:?}", self.state)
+  }
+}
+impl<State: fmt::Debug> Error for MissingOutput<State> {}
+
+impl<State, Input, Output> MooreMachine<State, Input, Output> {
+  /// Fails if the initial state or a transition target has no output
+  pub fn new(
+    initial: State,
+    transitions: HashMap<(State, Input), State>,
+    outputs: HashMap<State, Output>,
+  ) -> Result<Self, MissingOutput<State>>
+  where
+    State: Clone + Hash + Eq,
+  {
+    let missing = [&initial]
+      .into_iter()
+      .chain(transitions.values())
+      .find(|state| !outputs.contains_key(state));
+    if let Some(state) = missing {
+      return Err(MissingOutput {
+        state: state.clone(),
+      });
+    }
+    Ok(Self {
+      initial,
+      transitions,
+      outputs,
+    })
+  }
+
+  pub fn initial(&self) -> &State {
+    &self.initial
+  }
+
+  pub fn transitions(&self) -> &HashMap<(State, Input), State> {
+    &self.transitions
+  }
+
+  pub fn outputs(&self) -> &HashMap<State, Output> {
+    &self.outputs
+  }
+}
+
+impl<State, Input, Output> MooreMachine<State, Input, Output>
+where
+  State: Copy + Hash + Eq,
+  Input: Clone + Hash + Eq,
+  Output: Clone,
+{
+  /// Mealy machine emitting the output of the target state on every transition
+  pub fn to_mealy(&self) -> Mealy<State, Input, Output> {
+    let table = self
+      .transitions
+      .iter()
+      .map(|((from, input), to)| ((*from, input.clone()), (*to, self.outputs[to].clone())))
+      .collect();
+    Mealy::new(self.initial, table)
+  }
+}
+
+impl<State, Input, Output> Mealy<State, Input, Output>
+where
+  State: Copy + Hash + Eq,
+  Input: Clone + Hash + Eq,
+  Output: Clone + Hash + Eq,
+{
+  /// Moore machine whose states pair a Mealy state with the output of the transition entering it
+  ///
+  /// The initial state is paired with `initial_output`, which is never emitted by a driver.
+  /// Only reachable pairs are created.
+  pub fn to_moore(&self, initial_output: Output) -> MooreMachine<(State, Output), Input, Output> {
+    let mut outgoing: HashMap<State, Vec<_>> = HashMap::new();
+    for ((from, input), (to, output)) in self.table() {
+      outgoing.entry(*from).or_default().push((input, to, output));
+    }
+    let initial = (*self.initial(), initial_output);
+    let mut states = vec![initial.clone()];
+    let mut transitions = HashMap::new();
+    let mut outputs = HashMap::from([(initial.clone(), initial.1.clone())]);
+    while let Some(state) = states.pop() {
+      for &(input, to, output) in outgoing.get(&state.0).into_iter().flatten() {
+        let next = (*to, output.clone());
+        if !outputs.contains_key(&next) {
+          outputs.insert(next.clone(), output.clone());
+          states.push(next.clone());
+        }
+        transitions.insert((state.clone(), input.clone()), next);
+      }
+    }
+    // every target was given an output when it was discovered
+    debug_assert!(transitions
+      .values()
+      .all(|state| outputs.contains_key(state)));
+    MooreMachine {
+      initial,
+      transitions,
+      outputs,
+    }
+  }
+}
+
+/// Moore machine driver
+///
+/// Outputs the output of the entered state.
+///
+/// Zero-cost construction
+pub struct DriverMoore<'a, State, Input, Output> {
+  sm: &'a mut StateMachine<State>,
+  moore: &'a MooreMachine<State, Input, Output>,
+}
+impl<'a, State, Input, Output> DriverMoore<'a, State, Input, Output>
+where
+  State: Hash + Eq,
+{
+  pub fn new(
+    sm: &'a mut StateMachine<State>,
+    moore: &'a MooreMachine<State, Input, Output>,
+  ) -> Self {
+    Self { sm, moore }
+  }
+
+  /// Output of the current state
+  pub fn output(&self) -> &Output {
+    &self.moore.outputs[&self.sm.state]
+  }
+}
+
+impl<'a, State, Input, Output> Driver<Input, Output> for DriverMoore<'a, State, Input, Output>
+where
+  State: Copy + Hash + Eq,
+  Input: Hash + Eq,
+  Output: Copy,
+{
+  fn step(&mut self, input: Input) -> Output {
+    self.sm.state = self.moore.transitions[&(self.sm.state, input)];
+    *self.output()
+  }
+}
+
+impl<'a, State, Input, Output> TryDriver<Input, Output> for DriverMoore<'a, State, Input, Output>
+where
+  State: Copy + Hash + Eq,
+  Input: Hash + Eq,
+  Output: Copy,
+{
+  type State = State;
+  fn try_step(&mut self, input: Input) -> Result<Output, StepError<State, Input>> {
+    let key = (self.sm.state, input);
+    let transition = self.moore.transitions.get(&key);
+    match transition.and_then(|state| Some((*state, *self.moore.outputs.get(state)?))) {
+      Some((state, output)) => {
+        self.sm.state = state;
+        Ok(output)
+      }
+      None => Err(StepError {
+        state: key.0,
+        input: key.1,
+      }),
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{equivalent, DriverExt, DriverTransitionTable};
+
+  #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+  enum Light {
+    Red,
+    Green,
+    Yellow,
+  }
+
+  #[test]
+  fn trace_equivalence() {
+    let moore = MooreMachine::new(
+      Light::Red,
+      HashMap::from([
+        ((Light::Red, "tick"), Light::Green),
+        ((Light::Green, "tick"), Light::Yellow),
+        ((Light::Yellow, "tick"), Light::Red),
+        ((Light::Green, "stop"), Light::Yellow),
+        ((Light::Yellow, "stop"), Light::Red),
+        ((Light::Red, "stop"), Light::Red),
+      ]),
+      HashMap::from([(Light::Red, 0), (Light::Green, 2), (Light::Yellow, 1)]),
+    )
+    .unwrap();
+    let inputs = ["tick", "tick", "stop", "tick", "stop", "stop", "tick"];
+    let mut state_machine = StateMachine::new(*moore.initial());
+    let mut driver = DriverMoore::new(&mut state_machine, &moore);
+    assert_eq!(driver.output(), &0);
+    let trace = driver.run(inputs);
+    assert_eq!(trace, vec![2, 1, 0, 2, 1, 0, 2]);
+
+    let mealy = moore.to_mealy();
+    let mut state_machine = StateMachine::new(*mealy.initial());
+    let mut driver = DriverTransitionTable::new(&mut state_machine, mealy.table());
+    assert_eq!(driver.run(inputs), trace);
+
+    let round_trip = mealy.to_moore(moore.outputs()[moore.initial()]);
+    let mut state_machine = StateMachine::new(*round_trip.initial());
+    let mut driver = DriverMoore::new(&mut state_machine, &round_trip);
+    assert_eq!(driver.output(), &0);
+    assert_eq!(driver.run(inputs), trace);
+    assert_eq!(equivalent(&round_trip.to_mealy(), &mealy), Ok(()));
+  }
+
+  #[test]
+  fn to_moore_splits_states() {
+    // parity of the ones seen so far, the output names the input bit
+    let mealy = Mealy::new(
+      false,
+      HashMap::from([
+        ((false, 0), (false, 'a')),
+        ((false, 1), (true, 'b')),
+        ((true, 0), (true, 'a')),
+        ((true, 1), (false, 'b')),
+      ]),
+    );
+    let moore = mealy.to_moore('-');
+    assert_eq!(moore.outputs().len(), 5);
+    assert_eq!(equivalent(&moore.to_mealy(), &mealy), Ok(()));
+
+    let mut state_machine = StateMachine::new(*moore.initial());
+    let mut driver = DriverMoore::new(&mut state_machine, &moore);
+    assert_eq!(driver.try_step(1), Ok('b'));
+    assert_eq!(
+      driver.try_step(2),
+      Err(StepError {
+        state: (true, 'b'),
+        input: 2
+      })
+    );
+  }
+
+  #[test]
+  fn missing_output() {
+    let transitions = HashMap::from([((Light::Red, "tick"), Light::Green)]);
+    let outputs = HashMap::from([(Light::Red, 0)]);
+    assert_eq!(
+      MooreMachine::new(Light::Red, transitions.clone(), outputs.clone()).unwrap_err(),
+      MissingOutput {
+        state: Light::Green
+      }
+    );
+    assert_eq!(
+      MooreMachine::new(Light::Yellow, transitions, outputs)
+        .unwrap_err()
+        .to_string(),
+      "no output for state Yellow"
+    );
+  }
+}
@@ -0,0 +1,288 @@
+use crate::{Dfa, Driver, StateMachine};
+use std::{
+  collections::{HashMap, HashSet},
+  hash::Hash,
+};
+
+/// Nondeterministic finite acceptor with epsilon moves
+///
+/// Simulated on the set of active states.
+#[derive(Debug, Clone)]
+#[cfg_attr(
+  feature = "serde",
+  derive(serde::Serialize, serde::Deserialize),
+  serde(bound(
+    serialize = "State: serde::Serialize, Symbol: serde::Serialize",
+    deserialize = "State: serde::Deserialize<'de> + Hash + Eq, Symbol: serde::Deserialize<'de> + Hash + Eq"
+  ))
+)]
+pub struct Nfa<State, Symbol> {
+  initial: State,
+  #[cfg_attr(
+    feature = "serde",
+    serde(with = "crate::serde_table::nondeterministic")
+  )]
+  transitions: HashMap<(State, Symbol), HashSet<State>>,
+  #[cfg_attr(feature = "serde", serde(with = "crate::serde_table::epsilon"))]
+  epsilon: HashMap<State, HashSet<State>>,
+  accepting: HashSet<State>,
+}
+impl<State, Symbol> Nfa<State, Symbol> {
+  pub fn new(
+    initial: State,
+    transitions: HashMap<(State, Symbol), HashSet<State>>,
+    epsilon: HashMap<State, HashSet<State>>,
+    accepting: HashSet<State>,
+  ) -> Self {
+    Self {
+      initial,
+      transitions,
+      epsilon,
+      accepting,
+    }
+  }
+
+  pub fn initial(&self) -> &State {
+    &self.initial
+  }
+
+  pub fn transitions(&self) -> &HashMap<(State, Symbol), HashSet<State>> {
+    &self.transitions
+  }
+
+  pub fn epsilon(&self) -> &HashMap<State, HashSet<State>> {
+    &self.epsilon
+  }
+
+  pub fn accepting(&self) -> &HashSet<State> {
+    &self.accepting
+  }
+}
+
+impl<State, Symbol> Nfa<State, Symbol>
+where
+  State: Copy + Hash + Eq,
+  Symbol: Clone + Hash + Eq,
+{
+  /// All states reachable from `states` by epsilon moves only
+  pub fn epsilon_closure<States>(&self, states: States) -> HashSet<State>
+  where
+    States: IntoIterator<Item = State>,
+  {
+    let mut closure = HashSet::new();
+    let mut stack: Vec<State> = states.into_iter().collect();
+    while let Some(state) = stack.pop() {
+      if closure.insert(state) {
+        if let Some(targets) = self.epsilon.get(&state) {
+          stack.extend(targets.iter().copied());
+        }
+      }
+    }
+    closure
+  }
+
+  /// Epsilon closure of the initial state
+  pub fn initial_states(&self) -> HashSet<State> {
+    self.epsilon_closure([self.initial])
+  }
+
+  /// Symbol move followed by epsilon closure
+  pub fn step_states(&self, states: &HashSet<State>, symbol: &Symbol) -> HashSet<State> {
+    let targets = states
+      .iter()
+      .filter_map(|state| self.transitions.get(&(*state, symbol.clone())))
+      .flatten()
+      .copied();
+    self.epsilon_closure(targets)
+  }
+
+  pub fn is_accepting(&self, states: &HashSet<State>) -> bool {
+    states.iter().any(|state| self.accepting.contains(state))
+  }
+
+  /// Follows `word` starting in `states`, the empty set if every path dies
+  pub fn run_from<Word>(&self, states: HashSet<State>, word: Word) -> HashSet<State>
+  where
+    Word: IntoIterator<Item = Symbol>,
+  {
+    word
+      .into_iter()
+      .fold(self.epsilon_closure(states), |states, symbol| {
+        self.step_states(&states, &symbol)
+      })
+  }
+
+  pub fn accepts<Word>(&self, word: Word) -> bool
+  where
+    Word: IntoIterator<Item = Symbol>,
+  {
+    self.is_accepting(&self.run_from(self.initial_states(), word))
+  }
+
+  /// Powerset construction over the reachable subsets
+  ///
+  /// Returns the DFA together with the NFA states behind each DFA state.
+  /// Transitions into the empty subset are left out.
+  pub fn determinize(&self) -> (Dfa<usize, Symbol>, Vec<HashSet<State>>) {
+    let alphabet: Vec<Symbol> = self
+      .transitions
+      .keys()
+      .map(|(_, symbol)| symbol)
+      .collect::<HashSet<_>>()
+      .into_iter()
+      .cloned()
+      .collect();
+
+    let mut indices = HashMap::new();
+    let mut key = |states: &HashSet<State>| {
+      let mut key: Vec<usize> = states
+        .iter()
+        .map(|state| {
+          let len = indices.len();
+          *indices.entry(*state).or_insert(len)
+        })
+        .collect();
+      key.sort_unstable();
+      key
+    };
+
+    let initial = self.initial_states();
+    let mut ids = HashMap::from([(key(&initial), 0)]);
+    let mut subsets = vec![initial];
+    let mut transitions = HashMap::new();
+    let mut next = 0;
+    while next < subsets.len() {
+      for symbol in &alphabet {
+        let target = self.step_states(&subsets[next], symbol);
+        if target.is_empty() {
+          continue;
+        }
+        let id = *ids.entry(key(&target)).or_insert_with(|| {
+          subsets.push(target);
+          subsets.len() - 1
+        });
+        transitions.insert((next, symbol.clone()), id);
+      }
+      next += 1;
+    }
+
+    let accepting = (0..subsets.len())
+      .filter(|&id| self.is_accepting(&subsets[id]))
+      .collect();
+    (Dfa::new(0, transitions, accepting), subsets)
+  }
+}
+
+/// State machine driver with nondeterministic finite acceptor
+///
+/// The state machine holds the epsilon-closed set of active states.
+/// Outputs whether any active state is accepting.
+///
+/// Construction closes the active states under epsilon moves
+pub struct DriverNfa<'a, State, Symbol> {
+  sm: &'a mut StateMachine<HashSet<State>>,
+  nfa: &'a Nfa<State, Symbol>,
+}
+impl<'a, State, Symbol> DriverNfa<'a, State, Symbol>
+where
+  State: Copy + Hash + Eq,
+  Symbol: Clone + Hash + Eq,
+{
+  pub fn new(sm: &'a mut StateMachine<HashSet<State>>, nfa: &'a Nfa<State, Symbol>) -> Self {
+    sm.state = nfa.epsilon_closure(sm.state.iter().copied());
+    Self { sm, nfa }
+  }
+
+  pub fn is_accepting(&self) -> bool {
+    self.nfa.is_accepting(&self.sm.state)
+  }
+}
+
+impl<'a, State, Symbol> Driver<Symbol, bool> for DriverNfa<'a, State, Symbol>
+where
+  State: Copy + Hash + Eq,
+  Symbol: Clone + Hash + Eq,
+{
+  fn step(&mut self, input: Symbol) -> bool {
+    self.sm.state = self.nfa.step_states(&self.sm.state, &input);
+    self.is_accepting()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  /// Words over `a` and `b` ending in `ab`
+  fn ends_with_ab() -> Nfa<u8, char> {
+    let transitions = HashMap::from([
+      ((0, 'a'), HashSet::from([0, 1])),
+      ((0, 'b'), HashSet::from([0])),
+      ((1, 'b'), HashSet::from([2])),
+    ]);
+    Nfa::new(0, transitions, HashMap::new(), HashSet::from([2]))
+  }
+
+  #[test]
+  fn ends_with_ab_accepts() {
+    let nfa = ends_with_ab();
+    assert!(nfa.accepts("ab".chars()));
+    assert!(nfa.accepts("babab".chars()));
+    assert!(!nfa.accepts("".chars()));
+    assert!(!nfa.accepts("aba".chars()));
+    assert_eq!(
+      nfa.run_from(HashSet::from([1]), "a".chars()),
+      HashSet::new()
+    );
+  }
+
+  #[test]
+  fn epsilon_moves() {
+    // a* b*
+    let transitions = HashMap::from([
+      ((0, 'a'), HashSet::from([0])),
+      ((1, 'b'), HashSet::from([1])),
+    ]);
+    let epsilon = HashMap::from([(0, HashSet::from([1]))]);
+    let nfa = Nfa::new(0, transitions, epsilon, HashSet::from([1]));
+    assert_eq!(nfa.initial_states(), HashSet::from([0, 1]));
+    assert!(nfa.accepts("".chars()));
+    assert!(nfa.accepts("aabbb".chars()));
+    assert!(nfa.accepts("bb".chars()));
+    assert!(!nfa.accepts("aba".chars()));
+
+    // accepting through an epsilon move before the first step
+    let mut state_machine = StateMachine::new(HashSet::from([0]));
+    let mut driver = DriverNfa::new(&mut state_machine, &nfa);
+    assert!(driver.is_accepting());
+    assert!(driver.step('b'));
+    assert!(!driver.step('a'));
+  }
+
+  #[test]
+  fn ends_with_ab_driver() {
+    let nfa = ends_with_ab();
+    let mut state_machine = StateMachine::new(nfa.initial_states());
+    let mut driver = DriverNfa::new(&mut state_machine, &nfa);
+    assert!(!driver.step('a'));
+    assert!(driver.step('b'));
+    assert!(!driver.step('b'));
+    assert_eq!(state_machine.state(), &HashSet::from([0]));
+  }
+
+  #[test]
+  fn ends_with_ab_determinize() {
+    let nfa = ends_with_ab();
+    let (dfa, subsets) = nfa.determinize();
+    assert_eq!(subsets.len(), 3);
+    assert_eq!(subsets[*dfa.initial()], HashSet::from([0]));
+    for word in ["", "a", "ab", "ba", "abab", "bbab", "abba"] {
+      assert_eq!(dfa.accepts(word.chars()), nfa.accepts(word.chars()));
+    }
+    for (&(from, _), &to) in dfa.transitions() {
+      assert!(from < subsets.len() && to < subsets.len());
+    }
+    let accepting = &subsets[dfa.run_from(0, "aab".chars()).unwrap()];
+    assert_eq!(accepting, &HashSet::from([0, 2]));
+  }
+}
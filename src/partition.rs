@@ -0,0 +1,128 @@
+//! Hopcroft's partition refinement
+
+/// Coarsest partition of a complete deterministic automaton
+///
+/// States are `0..n`, symbols `0..k` and `delta[state * k + symbol]` is the target.
+/// `classes` assigns every state its initial class, equal classes start in one block.
+/// Returns the block of every state.
+pub(crate) fn hopcroft(n: usize, k: usize, delta: &[usize], classes: &[usize]) -> Vec<usize> {
+  debug_assert_eq!(delta.len(), n * k);
+  debug_assert_eq!(classes.len(), n);
+
+  // inverse transitions in compressed rows, indexed by `symbol * n + target`
+  let mut inverse_start = vec![0; k * n + 1];
+  for state in 0..n {
+    for symbol in 0..k {
+      inverse_start[symbol * n + delta[state * k + symbol] + 1] += 1;
+    }
+  }
+  for i in 0..k * n {
+    inverse_start[i + 1] += inverse_start[i];
+  }
+  let mut inverse = vec![0; n * k];
+  let mut fill = inverse_start.clone();
+  for state in 0..n {
+    for symbol in 0..k {
+      let row = symbol * n + delta[state * k + symbol];
+      inverse[fill[row]] = state;
+      fill[row] += 1;
+    }
+  }
+
+  // blocks are contiguous segments of `elements`, marked states at the front
+  let mut elements: Vec<usize> = (0..n).collect();
+  elements.sort_by_key(|&state| classes[state]);
+  let mut location = vec![0; n];
+  let mut block_of = vec![0; n];
+  let mut start = Vec::new();
+  let mut end = Vec::new();
+  for (i, &state) in elements.iter().enumerate() {
+    location[state] = i;
+    if i == 0 || classes[elements[i - 1]] != classes[state] {
+      start.push(i);
+      end.push(i);
+    }
+    block_of[state] = start.len() - 1;
+    *end.last_mut().unwrap() += 1;
+  }
+  let mut marked = vec![0; start.len()];
+
+  let mut pending = vec![true; start.len() * k];
+  let mut worklist: Vec<(usize, usize)> = (0..start.len())
+    .flat_map(|block| (0..k).map(move |symbol| (block, symbol)))
+    .collect();
+
+  let mut splitter = Vec::new();
+  let mut touched = Vec::new();
+  while let Some((block, symbol)) = worklist.pop() {
+    pending[block * k + symbol] = false;
+    splitter.clear();
+    splitter.extend_from_slice(&elements[start[block]..end[block]]);
+
+    for &target in &splitter {
+      let row = symbol * n + target;
+      for &state in &inverse[inverse_start[row]..inverse_start[row + 1]] {
+        let b = block_of[state];
+        let front = start[b] + marked[b];
+        if location[state] >= front {
+          let other = elements[front];
+          elements.swap(location[state], front);
+          location[other] = location[state];
+          location[state] = front;
+          if marked[b] == 0 {
+            touched.push(b);
+          }
+          marked[b] += 1;
+        }
+      }
+    }
+
+    for b in touched.drain(..) {
+      let count = marked[b];
+      marked[b] = 0;
+      if count == end[b] - start[b] {
+        continue;
+      }
+
+      let new = start.len();
+      start.push(start[b]);
+      end.push(start[b] + count);
+      marked.push(0);
+      start[b] += count;
+      for &state in &elements[start[new]..end[new]] {
+        block_of[state] = new;
+      }
+
+      pending.resize(pending.len() + k, false);
+      let smaller = if count <= end[b] - start[b] { new } else { b };
+      for symbol in 0..k {
+        let split = if pending[b * k + symbol] {
+          new
+        } else {
+          smaller
+        };
+        pending[split * k + symbol] = true;
+        worklist.push((split, symbol));
+      }
+    }
+  }
+
+  block_of
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn counter_modulo_six_by_parity() {
+    // increment modulo six, only parity observable
+    let delta: Vec<usize> = (0..6).map(|state| (state + 1) % 6).collect();
+    let classes: Vec<usize> = (0..6).map(|state| state % 2).collect();
+    let blocks = hopcroft(6, 1, &delta, &classes);
+    for state in 0..6 {
+      assert_eq!(blocks[state], blocks[state % 2]);
+    }
+    assert_ne!(blocks[0], blocks[1]);
+  }
+}
@@ -0,0 +1,1014 @@
+use crate::{Dfa, Nfa};
+use std::{
+  collections::{BTreeSet, HashMap, HashSet},
+  error::Error,
+  fmt,
+  hash::Hash,
+  iter::Peekable,
+  str::CharIndices,
+};
+
+/// Regular expression over a generic alphabet
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(
+  feature = "serde",
+  derive(serde::Serialize, serde::Deserialize),
+  serde(bound(deserialize = "Symbol: serde::Deserialize<'de> + Ord"))
+)]
+pub enum Regex<Symbol> {
+  /// Matches nothing
+  Empty,
+  /// Matches the empty word
+  Epsilon,
+  Symbol(Symbol),
+  /// Matches any single symbol of the set
+  Class(BTreeSet<Symbol>),
+  Concat(Vec<Regex<Symbol>>),
+  Alternation(Vec<Regex<Symbol>>),
+  Star(Box<Regex<Symbol>>),
+  Plus(Box<Regex<Symbol>>),
+  Optional(Box<Regex<Symbol>>),
+  /// Between `min` and `max` repetitions, unbounded without `max`
+  Repeat {
+    regex: Box<Regex<Symbol>>,
+    min: u32,
+    max: Option<u32>,
+  },
+}
+
+impl<Symbol: Clone> Regex<Symbol> {
+  /// Rewrites `Plus`, `Optional` and `Repeat` in terms of the other constructors
+  pub fn desugar(&self) -> Regex<Symbol> {
+    match self {
+      Regex::Empty | Regex::Epsilon | Regex::Symbol(_) | Regex::Class(_) => self.clone(),
+      Regex::Concat(regexes) => Regex::Concat(regexes.iter().map(Regex::desugar).collect()),
+      Regex::Alternation(regexes) => {
+        Regex::Alternation(regexes.iter().map(Regex::desugar).collect())
+      }
+      Regex::Star(regex) => Regex::Star(Box::new(regex.desugar())),
+      Regex::Plus(regex) => {
+        let regex = regex.desugar();
+        Regex::Concat(vec![regex.clone(), Regex::Star(Box::new(regex))])
+      }
+      Regex::Optional(regex) => Regex::Alternation(vec![regex.desugar(), Regex::Epsilon]),
+      Regex::Repeat { regex, min, max } => {
+        let regex = regex.desugar();
+        let mut regexes = vec![regex.clone(); *min as usize];
+        match max {
+          None => regexes.push(Regex::Star(Box::new(regex))),
+          Some(max) => {
+            let optional = Regex::Alternation(vec![regex, Regex::Epsilon]);
+            regexes.extend(std::iter::repeat_n(
+              optional,
+              max.saturating_sub(*min) as usize,
+            ));
+          }
+        }
+        Regex::Concat(regexes)
+      }
+    }
+  }
+}
+
+/// Simplifying constructors
+///
+/// They flatten nested operators, apply the identities of `Empty` and `Epsilon`
+/// and keep alternations sorted and free of duplicates.
+impl<Symbol: Clone + Ord> Regex<Symbol> {
+  pub fn is_nullable(&self) -> bool {
+    match self {
+      Regex::Empty | Regex::Symbol(_) | Regex::Class(_) => false,
+      Regex::Epsilon | Regex::Star(_) | Regex::Optional(_) => true,
+      Regex::Concat(regexes) => regexes.iter().all(Regex::is_nullable),
+      Regex::Alternation(regexes) => regexes.iter().any(Regex::is_nullable),
+      Regex::Plus(regex) => regex.is_nullable(),
+      Regex::Repeat { regex, min, .. } => *min == 0 || regex.is_nullable(),
+    }
+  }
+
+  /// Merges single symbols into one class, `r|()` becomes `r?`
+  pub fn alternation<Regexes>(regexes: Regexes) -> Self
+  where
+    Regexes: IntoIterator<Item = Self>,
+  {
+    let mut stack: Vec<Self> = regexes.into_iter().collect();
+    let mut regexes = BTreeSet::new();
+    let mut symbols = BTreeSet::new();
+    while let Some(regex) = stack.pop() {
+      match regex {
+        Regex::Empty => {}
+        Regex::Symbol(symbol) => {
+          symbols.insert(symbol);
+        }
+        Regex::Class(class) => symbols.extend(class),
+        Regex::Alternation(alternatives) => stack.extend(alternatives),
+        Regex::Optional(regex) => {
+          regexes.insert(Regex::Epsilon);
+          stack.push(*regex);
+        }
+        regex => {
+          regexes.insert(regex);
+        }
+      }
+    }
+    match symbols.len() {
+      0 => {}
+      1 => {
+        regexes.insert(Regex::Symbol(symbols.pop_first().unwrap()));
+      }
+      _ => {
+        regexes.insert(Regex::Class(symbols));
+      }
+    }
+
+    let epsilon = regexes.remove(&Regex::Epsilon);
+    let regex = match regexes.len() {
+      0 if epsilon => return Regex::Epsilon,
+      0 => return Regex::Empty,
+      1 => regexes.pop_first().unwrap(),
+      _ => Regex::Alternation(regexes.into_iter().collect()),
+    };
+    match epsilon {
+      true => Regex::optional(regex),
+      false => regex,
+    }
+  }
+
+  /// Turns `r r*` and `r* r` into `r+`
+  pub fn concat<Regexes>(regexes: Regexes) -> Self
+  where
+    Regexes: IntoIterator<Item = Self>,
+  {
+    let mut stack: Vec<Self> = regexes.into_iter().collect();
+    stack.reverse();
+    let mut regexes: Vec<Self> = Vec::new();
+    while let Some(regex) = stack.pop() {
+      match regex {
+        Regex::Empty => return Regex::Empty,
+        Regex::Epsilon => {}
+        Regex::Concat(parts) => stack.extend(parts.into_iter().rev()),
+        Regex::Star(regex) if regexes.last() == Some(&regex) => {
+          regexes.pop();
+          regexes.push(Regex::Plus(regex));
+        }
+        regex => match regexes.last() {
+          Some(Regex::Star(last)) if **last == regex => {
+            regexes.pop();
+            regexes.push(Regex::Plus(Box::new(regex)));
+          }
+          _ => regexes.push(regex),
+        },
+      }
+    }
+    match regexes.len() {
+      0 => Regex::Epsilon,
+      1 => regexes.pop().unwrap(),
+      _ => Regex::Concat(regexes),
+    }
+  }
+
+  pub fn star(regex: Self) -> Self {
+    match regex {
+      Regex::Empty | Regex::Epsilon => Regex::Epsilon,
+      Regex::Star(regex) | Regex::Plus(regex) | Regex::Optional(regex) => Regex::star(*regex),
+      regex => Regex::Star(Box::new(regex)),
+    }
+  }
+
+  pub fn plus(regex: Self) -> Self {
+    match regex {
+      Regex::Empty | Regex::Epsilon | Regex::Star(_) | Regex::Plus(_) => regex,
+      Regex::Optional(regex) => Regex::star(*regex),
+      regex => Regex::Plus(Box::new(regex)),
+    }
+  }
+
+  pub fn optional(regex: Self) -> Self {
+    match regex {
+      Regex::Empty => Regex::Epsilon,
+      Regex::Plus(regex) => Regex::star(*regex),
+      regex if regex.is_nullable() => regex,
+      regex => Regex::Optional(Box::new(regex)),
+    }
+  }
+
+  pub fn repeat(regex: Self, min: u32, max: Option<u32>) -> Self {
+    match (regex, min, max) {
+      (_, _, Some(0)) | (Regex::Epsilon, _, _) | (Regex::Empty, 0, _) => Regex::Epsilon,
+      (Regex::Empty, _, _) => Regex::Empty,
+      (regex, 1, Some(1)) => regex,
+      (regex, 0, Some(1)) => Regex::optional(regex),
+      (regex, 0, None) => Regex::star(regex),
+      (regex, 1, None) => Regex::plus(regex),
+      (regex, min, max) => Regex::Repeat {
+        regex: Box::new(regex),
+        min,
+        max,
+      },
+    }
+  }
+
+  /// Rebuilds the regex bottom-up with the simplifying constructors
+  pub fn simplify(&self) -> Self {
+    match self {
+      Regex::Empty | Regex::Epsilon | Regex::Symbol(_) => self.clone(),
+      Regex::Class(symbols) => Regex::alternation(symbols.iter().cloned().map(Regex::Symbol)),
+      Regex::Concat(regexes) => Regex::concat(regexes.iter().map(Regex::simplify)),
+      Regex::Alternation(regexes) => Regex::alternation(regexes.iter().map(Regex::simplify)),
+      Regex::Star(regex) => Regex::star(regex.simplify()),
+      Regex::Plus(regex) => Regex::plus(regex.simplify()),
+      Regex::Optional(regex) => Regex::optional(regex.simplify()),
+      Regex::Repeat { regex, min, max } => Regex::repeat(regex.simplify(), *min, *max),
+    }
+  }
+}
+
+/// Syntax accepted by [`Regex::parse`]
+///
+/// `Empty` is written as the empty class `[]` and `Epsilon` as `()`.
+/// Round trips require symbols that display as a single character.
+impl<Symbol: fmt::Display> fmt::Display for Regex<Symbol> {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    self.fmt_precedence(f, 0)
+  }
+}
+impl<Symbol: fmt::Display> Regex<Symbol> {
+  /// Precedence `0` is alternation, `1` concatenation and `2` repetition
+  fn fmt_precedence(&self, f: &mut fmt::Formatter<'_>, precedence: u8) -> fmt::Result {
+    fn escaped(symbol: impl fmt::Display, metacharacters: &str) -> String {
+      let symbol = symbol.to_string();
+      let mut chars = symbol.chars();
+      match (chars.next(), chars.next()) {
+        (Some(c), None) if metacharacters.contains(c) => format!("\\{c}"),
+        _ => symbol,
+      }
+    }
+
+    match self {
+      Regex::Empty => write!(f, "[]"),
+      Regex::Epsilon => write!(f, "()"),
+      Regex::Symbol(symbol) => write!(f, "{}", escaped(symbol, "|*+?(){}[]\\.^$")),
+      Regex::Class(symbols) => {
+        write!(f, "[")?;
+        for symbol in symbols {
+          write!(f, "{}", escaped(symbol, "[]\\-^"))?;
+        }
+        write!(f, "]")
+      }
+      Regex::Concat(regexes) | Regex::Alternation(regexes) => {
+        let (own, separator) = match self {
+          Regex::Concat(_) => (1, ""),
+          _ => (0, "|"),
+        };
+        if precedence > own {
+          write!(f, "(")?;
+        }
+        for (i, regex) in regexes.iter().enumerate() {
+          if i > 0 {
+            write!(f, "{separator}")?;
+          }
+          regex.fmt_precedence(f, own)?;
+        }
+        if precedence > own {
+          write!(f, ")")?;
+        }
+        Ok(())
+      }
+      Regex::Star(regex) => {
+        regex.fmt_precedence(f, 2)?;
+        write!(f, "*")
+      }
+      Regex::Plus(regex) => {
+        regex.fmt_precedence(f, 2)?;
+        write!(f, "+")
+      }
+      Regex::Optional(regex) => {
+        regex.fmt_precedence(f, 2)?;
+        write!(f, "?")
+      }
+      Regex::Repeat { regex, min, max } => {
+        regex.fmt_precedence(f, 2)?;
+        match max {
+          Some(max) if max == min => write!(f, "{{{min}}}"),
+          Some(max) => write!(f, "{{{min},{max}}}"),
+          None => write!(f, "{{{min},}}"),
+        }
+      }
+    }
+  }
+}
+
+impl<Symbol> Regex<Symbol>
+where
+  Symbol: TryFrom<char> + Ord,
+{
+  /// Parses the usual syntax with literals mapped through `TryFrom<char>`
+  ///
+  /// Supports `|`, `*`, `+`, `?`, `{n}`, `{n,}`, `{n,m}`, groups, classes like `[a-z_]`
+  /// and `\` to escape a metacharacter.
+  pub fn parse(pattern: &str) -> Result<Self, ParseError> {
+    let mut parser = Parser {
+      chars: pattern.char_indices().peekable(),
+      len: pattern.len(),
+    };
+    let regex = parser.alternation()?;
+    match parser.chars.next() {
+      None => Ok(regex),
+      Some((position, c)) => Err(ParseError::new(position, ParseErrorKind::Unexpected(c))),
+    }
+  }
+}
+
+struct Parser<'a> {
+  chars: Peekable<CharIndices<'a>>,
+  len: usize,
+}
+impl Parser<'_> {
+  fn position(&mut self) -> usize {
+    self
+      .chars
+      .peek()
+      .map_or(self.len, |&(position, _)| position)
+  }
+
+  fn symbol<Symbol: TryFrom<char>>(position: usize, c: char) -> Result<Symbol, ParseError> {
+    Symbol::try_from(c).map_err(|_| ParseError::new(position, ParseErrorKind::InvalidSymbol(c)))
+  }
+
+  fn alternation<Symbol>(&mut self) -> Result<Regex<Symbol>, ParseError>
+  where
+    Symbol: TryFrom<char> + Ord,
+  {
+    let mut regexes = vec![self.concat()?];
+    while self.chars.next_if(|&(_, c)| c == '|').is_some() {
+      regexes.push(self.concat()?);
+    }
+    Ok(match regexes.len() {
+      1 => regexes.pop().unwrap(),
+      _ => Regex::Alternation(regexes),
+    })
+  }
+
+  fn concat<Symbol>(&mut self) -> Result<Regex<Symbol>, ParseError>
+  where
+    Symbol: TryFrom<char> + Ord,
+  {
+    let mut regexes = Vec::new();
+    while self
+      .chars
+      .peek()
+      .is_some_and(|&(_, c)| c != '|' && c != ')')
+    {
+      regexes.push(self.repetition()?);
+    }
+    Ok(match regexes.len() {
+      0 => Regex::Epsilon,
+      1 => regexes.pop().unwrap(),
+      _ => Regex::Concat(regexes),
+    })
+  }
+
+  fn repetition<Symbol>(&mut self) -> Result<Regex<Symbol>, ParseError>
+  where
+    Symbol: TryFrom<char> + Ord,
+  {
+    let mut regex = self.atom()?;
+    while let Some(&(position, c)) = self.chars.peek() {
+      regex = match c {
+        '*' => Regex::Star(Box::new(regex)),
+        '+' => Regex::Plus(Box::new(regex)),
+        '?' => Regex::Optional(Box::new(regex)),
+        '{' => {
+          self.chars.next();
+          let min = self.number()?;
+          let max = match self.chars.next_if(|&(_, c)| c == ',') {
+            None => Some(min),
+            Some(_) => match self.chars.peek() {
+              Some((_, '}')) => None,
+              _ => Some(self.number()?),
+            },
+          };
+          self.expect('}')?;
+          if max.is_some_and(|max| max < min) {
+            return Err(ParseError::new(position, ParseErrorKind::InvalidRepetition));
+          }
+          regex = Regex::Repeat {
+            regex: Box::new(regex),
+            min,
+            max,
+          };
+          continue;
+        }
+        _ => break,
+      };
+      self.chars.next();
+    }
+    Ok(regex)
+  }
+
+  fn atom<Symbol>(&mut self) -> Result<Regex<Symbol>, ParseError>
+  where
+    Symbol: TryFrom<char> + Ord,
+  {
+    let position = self.position();
+    let Some((_, c)) = self.chars.next() else {
+      return Err(ParseError::new(position, ParseErrorKind::UnexpectedEnd));
+    };
+    match c {
+      '(' => {
+        let regex = self.alternation()?;
+        self.expect(')')?;
+        Ok(regex)
+      }
+      '[' => self.class(position),
+      '\\' => {
+        let (position, c) = self.escaped()?;
+        Ok(Regex::Symbol(Self::symbol(position, c)?))
+      }
+      '.' | '^' | '$' => Err(ParseError::new(position, ParseErrorKind::Unsupported(c))),
+      '*' | '+' | '?' | '{' | '}' | ']' => {
+        Err(ParseError::new(position, ParseErrorKind::Unexpected(c)))
+      }
+      c => Ok(Regex::Symbol(Self::symbol(position, c)?)),
+    }
+  }
+
+  fn class<Symbol>(&mut self, start: usize) -> Result<Regex<Symbol>, ParseError>
+  where
+    Symbol: TryFrom<char> + Ord,
+  {
+    if let Some((position, '^')) = self.chars.peek().copied() {
+      return Err(ParseError::new(position, ParseErrorKind::Unsupported('^')));
+    }
+    let mut symbols = BTreeSet::new();
+    loop {
+      let (position, c) = match self.chars.next() {
+        None => return Err(ParseError::new(start, ParseErrorKind::UnclosedClass)),
+        Some((_, ']')) => break,
+        Some((_, '\\')) => self.escaped()?,
+        Some(next) => next,
+      };
+      if self.chars.next_if(|&(_, c)| c == '-').is_none() {
+        symbols.insert(Self::symbol(position, c)?);
+        continue;
+      }
+      if let Some((_, ']')) = self.chars.peek() {
+        symbols.insert(Self::symbol(position, c)?);
+        symbols.insert(Self::symbol(position, '-')?);
+        continue;
+      }
+      let (_, last) = match self.chars.next() {
+        None => return Err(ParseError::new(start, ParseErrorKind::UnclosedClass)),
+        Some((_, '\\')) => self.escaped()?,
+        Some(next) => next,
+      };
+      if last < c {
+        return Err(ParseError::new(position, ParseErrorKind::InvalidRange));
+      }
+      for c in c..=last {
+        symbols.insert(Self::symbol(position, c)?);
+      }
+    }
+    Ok(Regex::Class(symbols))
+  }
+
+  fn escaped(&mut self) -> Result<(usize, char), ParseError> {
+    let position = self.position();
+    self
+      .chars
+      .next()
+      .ok_or(ParseError::new(position, ParseErrorKind::UnexpectedEnd))
+  }
+
+  fn number(&mut self) -> Result<u32, ParseError> {
+    let position = self.position();
+    let mut digits = String::new();
+    while let Some((_, c)) = self.chars.next_if(|(_, c)| c.is_ascii_digit()) {
+      digits.push(c);
+    }
+    digits
+      .parse()
+      .map_err(|_| ParseError::new(position, ParseErrorKind::InvalidRepetition))
+  }
+
+  fn expect(&mut self, expected: char) -> Result<(), ParseError> {
+    let position = self.position();
+    match self.chars.next() {
+      Some((_, c)) if c == expected => Ok(()),
+      Some((_, c)) => Err(ParseError::new(position, ParseErrorKind::Unexpected(c))),
+      None => Err(ParseError::new(position, ParseErrorKind::UnexpectedEnd)),
+    }
+  }
+}
+
+/// Invalid regular expression syntax
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+  /// Byte offset into the pattern
+  pub position: usize,
+  pub kind: ParseErrorKind,
+}
+impl ParseError {
+  fn new(position: usize, kind: ParseErrorKind) -> Self {
+    Self { position, kind }
+  }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseErrorKind {
+  UnexpectedEnd,
+  Unexpected(char),
+  /// Syntax that needs to know the whole alphabet
+  Unsupported(char),
+  /// Character not representable as a symbol
+  InvalidSymbol(char),
+  UnclosedClass,
+  InvalidRange,
+  InvalidRepetition,
+}
+
+impl fmt::Display for ParseError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self.kind {
+      ParseErrorKind::UnexpectedEnd => write!(f, "unexpected end of pattern")?,
+      ParseErrorKind::Unexpected(c) => write!(f, "unexpected {c:?}")?,
+      ParseErrorKind::Unsupported(c) => write!(f, "unsupported {c:?}")?,
+      ParseErrorKind::InvalidSymbol(c) => write!(f, "{c:?} is not a symbol of the alphabet")?,
+      ParseErrorKind::UnclosedClass => write!(f, "unclosed class")?,
+      ParseErrorKind::InvalidRange => write!(f, "invalid class range")?,
+      ParseErrorKind::InvalidRepetition => write!(f, "invalid repetition")?,
+    }
+    write!(f, " at position {}", self.position)
+  }
+}
+impl Error for ParseError {}
+
+impl<Symbol> Regex<Symbol>
+where
+  Symbol: Clone + Hash + Eq,
+{
+  /// Epsilon-NFA by Thompson's construction
+  ///
+  /// Every state has at most two outgoing transitions.
+  pub fn thompson(&self) -> Nfa<usize, Symbol> {
+    let mut thompson = Thompson {
+      states: 0,
+      transitions: HashMap::new(),
+      epsilon: HashMap::new(),
+    };
+    let (initial, accepting) = thompson.fragment(&self.desugar());
+    Nfa::new(
+      initial,
+      thompson.transitions,
+      thompson.epsilon,
+      HashSet::from([accepting]),
+    )
+  }
+
+  /// Epsilon-free NFA by Glushkov's position construction
+  ///
+  /// State `0` is initial, every other state is a position of a symbol in the regex.
+  pub fn glushkov(&self) -> Nfa<usize, Symbol> {
+    let mut glushkov = Glushkov {
+      positions: vec![Vec::new()],
+      follow: vec![HashSet::new()],
+    };
+    let (nullable, first, last) = glushkov.positions(&self.desugar());
+
+    let mut transitions: HashMap<_, HashSet<usize>> = HashMap::new();
+    let first: HashSet<usize> = first.into_iter().collect();
+    let origins = std::iter::once((0, &first)).chain(glushkov.follow.iter().enumerate());
+    for (from, targets) in origins {
+      for &to in targets {
+        for symbol in &glushkov.positions[to] {
+          transitions
+            .entry((from, symbol.clone()))
+            .or_default()
+            .insert(to);
+        }
+      }
+    }
+    let mut accepting: HashSet<usize> = last.into_iter().collect();
+    if nullable {
+      accepting.insert(0);
+    }
+    Nfa::new(0, transitions, HashMap::new(), accepting)
+  }
+
+  /// Minimal DFA through Glushkov's construction and determinization
+  pub fn to_dfa(&self) -> Dfa<usize, Symbol> {
+    self.glushkov().determinize().0.minimize().0
+  }
+}
+
+impl<State, Symbol> Dfa<State, Symbol>
+where
+  State: Copy + Hash + Eq,
+  Symbol: Clone + Ord,
+{
+  /// Equivalent regex by state elimination
+  pub fn to_regex(&self) -> Regex<Symbol> {
+    let mut indices = HashMap::from([(*self.initial(), 0)]);
+    let mut index = |state: State| {
+      let len = indices.len();
+      *indices.entry(state).or_insert(len)
+    };
+    let edges: Vec<_> = self
+      .transitions()
+      .iter()
+      .map(|((from, symbol), to)| (index(*from), Regex::Symbol(symbol.clone()), index(*to)))
+      .collect();
+    let accepting: Vec<_> = self.accepting().iter().map(|state| index(*state)).collect();
+    eliminate(indices.len(), 0, accepting, edges)
+  }
+}
+
+impl<State, Symbol> Nfa<State, Symbol>
+where
+  State: Copy + Hash + Eq,
+  Symbol: Clone + Ord,
+{
+  /// Equivalent regex by state elimination
+  pub fn to_regex(&self) -> Regex<Symbol> {
+    let mut indices = HashMap::from([(*self.initial(), 0)]);
+    let mut index = |state: State| {
+      let len = indices.len();
+      *indices.entry(state).or_insert(len)
+    };
+    let mut edges = Vec::new();
+    for ((from, symbol), targets) in self.transitions() {
+      for to in targets {
+        edges.push((index(*from), Regex::Symbol(symbol.clone()), index(*to)));
+      }
+    }
+    for (from, targets) in self.epsilon() {
+      for to in targets {
+        edges.push((index(*from), Regex::Epsilon, index(*to)));
+      }
+    }
+    let accepting: Vec<_> = self.accepting().iter().map(|state| index(*state)).collect();
+    eliminate(indices.len(), 0, accepting, edges)
+  }
+}
+
+/// Brzozowski-McCluskey state elimination on states `0..n`
+///
+/// States with the fewest paths through them are eliminated first,
+/// which keeps the intermediate regexes small.
+fn eliminate<Symbol: Clone + Ord>(
+  n: usize,
+  initial: usize,
+  accepting: Vec<usize>,
+  edges: Vec<(usize, Regex<Symbol>, usize)>,
+) -> Regex<Symbol> {
+  let (start, end) = (n, n + 1);
+  let mut graph: HashMap<(usize, usize), Regex<Symbol>> = HashMap::new();
+  let add = |graph: &mut HashMap<_, _>, from, regex, to| {
+    let regex = match graph.remove(&(from, to)) {
+      Some(previous) => Regex::alternation([previous, regex]),
+      None => regex,
+    };
+    graph.insert((from, to), regex);
+  };
+  add(&mut graph, start, Regex::Epsilon, initial);
+  for state in accepting {
+    add(&mut graph, state, Regex::Epsilon, end);
+  }
+  for (from, regex, to) in edges {
+    add(&mut graph, from, regex, to);
+  }
+
+  let mut remaining: BTreeSet<usize> = (0..n).collect();
+  while !remaining.is_empty() {
+    let degree = |state: usize| {
+      let incoming = graph
+        .keys()
+        .filter(|&&(p, q)| q == state && p != state)
+        .count();
+      let outgoing = graph
+        .keys()
+        .filter(|&&(p, q)| p == state && q != state)
+        .count();
+      incoming * outgoing
+    };
+    let state = *remaining
+      .iter()
+      .min_by_key(|&&state| degree(state))
+      .unwrap();
+    remaining.remove(&state);
+
+    let repeat = graph
+      .remove(&(state, state))
+      .map_or(Regex::Epsilon, Regex::star);
+    let incoming: Vec<_> = graph
+      .keys()
+      .filter(|&&(_, q)| q == state)
+      .copied()
+      .collect();
+    let outgoing: Vec<_> = graph
+      .keys()
+      .filter(|&&(p, _)| p == state)
+      .copied()
+      .collect();
+    let incoming: Vec<_> = incoming
+      .into_iter()
+      .map(|key| (key.0, graph.remove(&key).unwrap()))
+      .collect();
+    let outgoing: Vec<_> = outgoing
+      .into_iter()
+      .map(|key| (key.1, graph.remove(&key).unwrap()))
+      .collect();
+    for (from, before) in &incoming {
+      for (to, after) in &outgoing {
+        let regex = Regex::concat([before.clone(), repeat.clone(), after.clone()]);
+        add(&mut graph, *from, regex, *to);
+      }
+    }
+  }
+  graph.remove(&(start, end)).unwrap_or(Regex::Empty)
+}
+
+struct Thompson<Symbol> {
+  states: usize,
+  transitions: HashMap<(usize, Symbol), HashSet<usize>>,
+  epsilon: HashMap<usize, HashSet<usize>>,
+}
+impl<Symbol: Clone + Hash + Eq> Thompson<Symbol> {
+  fn state(&mut self) -> usize {
+    self.states += 1;
+    self.states - 1
+  }
+
+  fn epsilon(&mut self, from: usize, to: usize) {
+    self.epsilon.entry(from).or_default().insert(to);
+  }
+
+  /// Start and accepting state of the sub-automaton
+  fn fragment(&mut self, regex: &Regex<Symbol>) -> (usize, usize) {
+    let (start, end) = (self.state(), self.state());
+    match regex {
+      Regex::Empty => {}
+      Regex::Epsilon => self.epsilon(start, end),
+      Regex::Symbol(symbol) => {
+        self
+          .transitions
+          .entry((start, symbol.clone()))
+          .or_default()
+          .insert(end);
+      }
+      Regex::Class(symbols) => {
+        for symbol in symbols {
+          self
+            .transitions
+            .entry((start, symbol.clone()))
+            .or_default()
+            .insert(end);
+        }
+      }
+      Regex::Concat(regexes) => {
+        let mut last = start;
+        for regex in regexes {
+          let (first, next) = self.fragment(regex);
+          self.epsilon(last, first);
+          last = next;
+        }
+        self.epsilon(last, end);
+      }
+      Regex::Alternation(regexes) => {
+        for regex in regexes {
+          let (first, last) = self.fragment(regex);
+          self.epsilon(start, first);
+          self.epsilon(last, end);
+        }
+      }
+      Regex::Star(regex) => {
+        let (first, last) = self.fragment(regex);
+        self.epsilon(start, first);
+        self.epsilon(start, end);
+        self.epsilon(last, first);
+        self.epsilon(last, end);
+      }
+      Regex::Plus(_) | Regex::Optional(_) | Regex::Repeat { .. } => {
+        unreachable!("desugared")
+      }
+    }
+    (start, end)
+  }
+}
+
+struct Glushkov<Symbol> {
+  /// Symbols matched at each position, position `0` is the initial state
+  positions: Vec<Vec<Symbol>>,
+  follow: Vec<HashSet<usize>>,
+}
+impl<Symbol: Clone> Glushkov<Symbol> {
+  fn position(&mut self, symbols: Vec<Symbol>) -> (bool, Vec<usize>, Vec<usize>) {
+    self.positions.push(symbols);
+    self.follow.push(HashSet::new());
+    let position = self.positions.len() - 1;
+    (false, vec![position], vec![position])
+  }
+
+  /// Nullability, first and last positions
+  fn positions(&mut self, regex: &Regex<Symbol>) -> (bool, Vec<usize>, Vec<usize>) {
+    match regex {
+      Regex::Empty => (false, Vec::new(), Vec::new()),
+      Regex::Epsilon => (true, Vec::new(), Vec::new()),
+      Regex::Symbol(symbol) => self.position(vec![symbol.clone()]),
+      Regex::Class(symbols) => self.position(symbols.iter().cloned().collect()),
+      Regex::Concat(regexes) => {
+        let (mut nullable, mut first, mut last) = (true, Vec::new(), Vec::<usize>::new());
+        for regex in regexes {
+          let (next_nullable, next_first, next_last) = self.positions(regex);
+          for &position in &last {
+            self.follow[position].extend(next_first.iter().copied());
+          }
+          if nullable {
+            first.extend(&next_first);
+          }
+          if !next_nullable {
+            last.clear();
+          }
+          last.extend(next_last);
+          nullable &= next_nullable;
+        }
+        (nullable, first, last)
+      }
+      Regex::Alternation(regexes) => {
+        let (mut nullable, mut first, mut last) = (false, Vec::new(), Vec::new());
+        for regex in regexes {
+          let (next_nullable, next_first, next_last) = self.positions(regex);
+          nullable |= next_nullable;
+          first.extend(next_first);
+          last.extend(next_last);
+        }
+        (nullable, first, last)
+      }
+      Regex::Star(regex) => {
+        let (_, first, last) = self.positions(regex);
+        for &position in &last {
+          self.follow[position].extend(first.iter().copied());
+        }
+        (true, first, last)
+      }
+      Regex::Plus(_) | Regex::Optional(_) | Regex::Repeat { .. } => {
+        unreachable!("desugared")
+      }
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{equivalent, Driver, DriverDfa, StateMachine};
+
+  #[test]
+  fn parse_syntax() {
+    let regex = Regex::<char>::parse("a(b|c)*[x-z_]+[a-]?e{2,3}").unwrap();
+    let Regex::Concat(regexes) = &regex else {
+      panic!("expected concatenation");
+    };
+    assert_eq!(regexes.len(), 5);
+    assert_eq!(regexes[0], Regex::Symbol('a'));
+    assert_eq!(
+      regexes[2],
+      Regex::Plus(Box::new(Regex::Class(BTreeSet::from(['_', 'x', 'y', 'z']))))
+    );
+    assert_eq!(
+      regexes[3],
+      Regex::Optional(Box::new(Regex::Class(BTreeSet::from(['-', 'a']))))
+    );
+    assert_eq!(
+      regexes[4],
+      Regex::Repeat {
+        regex: Box::new(Regex::Symbol('e')),
+        min: 2,
+        max: Some(3),
+      }
+    );
+    assert_eq!(
+      Regex::<u8>::parse(r"\*|"),
+      Ok(Regex::Alternation(vec![
+        Regex::Symbol(b'*'),
+        Regex::Epsilon
+      ]))
+    );
+
+    let error = |pattern| Regex::<u8>::parse(pattern).unwrap_err();
+    assert_eq!(error("(ab").kind, ParseErrorKind::UnexpectedEnd);
+    assert_eq!(error("a)").position, 1);
+    assert_eq!(error("[z-a]").kind, ParseErrorKind::InvalidRange);
+    assert_eq!(error("a{3,1}").kind, ParseErrorKind::InvalidRepetition);
+    assert_eq!(error("[ab").kind, ParseErrorKind::UnclosedClass);
+    assert_eq!(error("a.").kind, ParseErrorKind::Unsupported('.'));
+    assert_eq!(error("aλ").kind, ParseErrorKind::InvalidSymbol('λ'));
+    assert_eq!(error("*").to_string(), "unexpected '*' at position 0");
+  }
+
+  #[test]
+  fn constructions_agree() {
+    let patterns = [
+      "",
+      "a",
+      "ab|c",
+      "(a|b)*abb",
+      "a+b?c*",
+      "[a-c]{2}",
+      "(ab){1,}|c{0,2}",
+      "((a|)b)*",
+    ];
+    let words = [
+      "", "a", "b", "c", "ab", "abb", "aabb", "ac", "bc", "cc", "abab", "bab",
+    ];
+    for pattern in patterns {
+      let regex = Regex::<char>::parse(pattern).unwrap();
+      let thompson = regex.thompson();
+      let glushkov = regex.glushkov();
+      assert!(glushkov.epsilon().is_empty());
+      for word in words {
+        assert_eq!(
+          thompson.accepts(word.chars()),
+          glushkov.accepts(word.chars()),
+          "{pattern} on {word}"
+        );
+      }
+      let dfa = regex.to_dfa();
+      assert_eq!(
+        equivalent(&dfa, &thompson.determinize().0),
+        Ok(()),
+        "{pattern}"
+      );
+    }
+  }
+
+  #[test]
+  fn compiled_driver() {
+    let dfa = Regex::<char>::parse("(a|b)*abb").unwrap().to_dfa();
+    assert_eq!(dfa.accepting().len(), 1);
+    assert_eq!(dfa.transitions().len(), 8);
+
+    let mut state_machine = StateMachine::new(*dfa.initial());
+    let mut driver = DriverDfa::new(&mut state_machine, &dfa);
+    let outputs: Vec<bool> = "babba".chars().map(|c| driver.step(c)).collect();
+    assert_eq!(outputs, vec![false, false, false, true, false]);
+  }
+
+  #[test]
+  fn simplify_and_display() {
+    let regex = Regex::<char>::parse("(a|b|a|[]|())c()d*d{0,1}(e{1,}|f{0,})").unwrap();
+    assert_eq!(regex.simplify().to_string(), "[ab]?cd*d?(f*|e+)");
+    assert_eq!(
+      Regex::<char>::parse(r"[]|()|\*[\-]").unwrap().to_string(),
+      r"[]|()|\*[\-]"
+    );
+    assert_eq!(
+      Regex::<char>::parse("(a|)*").unwrap().simplify(),
+      Regex::Star(Box::new(Regex::Symbol('a')))
+    );
+    for pattern in ["aa*", "a*a"] {
+      let regex = Regex::<char>::parse(pattern).unwrap();
+      let simplified = regex.simplify();
+      assert_eq!(simplified.to_string(), "a+");
+      assert_eq!(equivalent(&simplified.to_dfa(), &regex.to_dfa()), Ok(()));
+      assert!(!simplified.to_dfa().accepts("".chars()));
+    }
+  }
+
+  #[test]
+  fn state_elimination() {
+    let dfa = Regex::<char>::parse("ab*").unwrap().to_dfa();
+    assert_eq!(dfa.to_regex().to_string(), "ab*");
+
+    let patterns = [
+      "",
+      "(a|b)*abb",
+      "a+b?c*",
+      "[a-c]{2}",
+      "(ab){1,}|c{0,2}",
+      "((a|)b)*",
+    ];
+    for pattern in patterns {
+      let dfa = Regex::<char>::parse(pattern).unwrap().to_dfa();
+      let regex = dfa.to_regex();
+      let reparsed = Regex::<char>::parse(&regex.to_string()).unwrap();
+      assert_eq!(
+        equivalent(&dfa, &reparsed.to_dfa()),
+        Ok(()),
+        "{pattern} as {regex}"
+      );
+
+      let thompson = Regex::<char>::parse(pattern).unwrap().thompson();
+      let regex = thompson.to_regex();
+      assert_eq!(
+        equivalent(&dfa, &regex.to_dfa()),
+        Ok(()),
+        "{pattern} as {regex}"
+      );
+    }
+    assert_eq!(
+      Dfa::<u8, char>::new(0, HashMap::new(), HashSet::new()).to_regex(),
+      Regex::Empty
+    );
+  }
+}
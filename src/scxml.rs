@@ -0,0 +1,1035 @@
+//! SCXML (W3C State Chart XML) export and import
+//!
+//! Covers the flat subset: top-level `<state>` and `<final>` elements with
+//! `<transition event="..." target="..."/>` children and the `initial` attribute of `<scxml>`.
+//! A [`Dfa`] maps states to states and symbols to events. Accepting states become `<final>`.
+//! A `<final>` cannot have transitions, so an accepting state with transitions stays a `<state>`
+//! and declares `<datamodel><data id="{id}.accepting" expr="true"/></datamodel>` as its first child,
+//! with `{id}` the id of the state.
+//! A [`Mealy`] machine sends the output of a transition to its parent session,
+//! `<send event="..." target="#_parent"/>` as the only child of the `<transition>`.
+//! Anything else is rejected with its position instead of being dropped.
+
+use crate::{Dfa, Mealy};
+use std::{
+  collections::{hash_map::Entry, BTreeMap, HashMap},
+  error::Error,
+  fmt::{self, Display, Write},
+  hash::Hash,
+  iter::Peekable,
+  str::{Chars, FromStr},
+};
+
+const NAMESPACE: &str = "http://www.w3.org/2005/07/scxml";
+/// Send target of Mealy outputs
+const PARENT: &str = "#_parent";
+/// Suffix of the `<data>` id marking an accepting `<state>`
+const ACCEPTING: &str = ".accepting";
+
+/// Invalid or unsupported SCXML input
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScxmlError {
+  /// One-based line of the offending markup, zero when exporting
+  pub line: usize,
+  /// One-based column of the offending markup, counted in characters
+  pub column: usize,
+  pub kind: ScxmlErrorKind,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ScxmlErrorKind {
+  UnexpectedEnd,
+  Unexpected(char),
+  /// Closing tag that does not match the open element
+  Mismatched {
+    expected: String,
+    found: String,
+  },
+  UnknownEntity(String),
+  DuplicateAttribute(String),
+  /// Non-whitespace text content
+  Text,
+  /// Elements outside the flat subset, e.g. `<parallel>` or `<onentry>`
+  UnsupportedElement(String),
+  /// Attributes outside the flat subset, e.g. `cond`
+  UnsupportedAttribute(String),
+  MissingAttribute(&'static str),
+  /// Event descriptors that need matching, `*` or `error.*`
+  UnsupportedEvent(String),
+  DuplicateState(String),
+  UnknownState(String),
+  InvalidState(String),
+  InvalidEvent(String),
+  /// Second transition of a state for the same event, never taken in SCXML
+  DuplicateTransition {
+    state: String,
+    event: String,
+  },
+  InvalidOutput(String),
+  /// Mealy transition without `<send>` of its output
+  MissingOutput,
+  /// `<send>` target other than `#_parent`
+  UnsupportedTarget(String),
+  /// `<data>` other than the acceptance marker of its state
+  InvalidData(String),
+}
+
+impl Display for ScxmlError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match &self.kind {
+      ScxmlErrorKind::UnexpectedEnd => write!(f, "unexpected end of input")?,
+      ScxmlErrorKind::Unexpected(c) => write!(f, "unexpected {c:?}")?,
+      ScxmlErrorKind::Mismatched { expected, found } => {
+        write!(f, "expected </{expected}>, found </{found}>")?
+      }
+      ScxmlErrorKind::UnknownEntity(entity) => write!(f, "unknown entity &{entity};")?,
+      ScxmlErrorKind::DuplicateAttribute(name) => write!(f, "duplicate attribute {name:?}")?,
+      ScxmlErrorKind::Text => write!(f, "unexpected text content")?,
+      ScxmlErrorKind::UnsupportedElement(name) => write!(f, "<{name}> is not supported")?,
+      ScxmlErrorKind::UnsupportedAttribute(name) => {
+        write!(f, "attribute {name:?} is not supported")?
+      }
+      ScxmlErrorKind::MissingAttribute(name) => write!(f, "missing attribute {name:?}")?,
+      ScxmlErrorKind::UnsupportedEvent(event) => {
+        write!(f, "event descriptor {event:?} is not supported")?
+      }
+      ScxmlErrorKind::DuplicateState(id) => write!(f, "duplicate state {id:?}")?,
+      ScxmlErrorKind::UnknownState(id) => write!(f, "unknown state {id:?}")?,
+      ScxmlErrorKind::InvalidState(id) => write!(f, "invalid state {id:?}")?,
+      ScxmlErrorKind::InvalidEvent(event) => write!(f, "invalid event {event:?}")?,
+      ScxmlErrorKind::DuplicateTransition { state, event } => {
+        write!(f, "second transition from {state:?} on {event:?}")?
+      }
+      ScxmlErrorKind::InvalidOutput(output) => write!(f, "invalid output {output:?}")?,
+      ScxmlErrorKind::MissingOutput => write!(f, "missing <send> of the output")?,
+      ScxmlErrorKind::UnsupportedTarget(target) => {
+        write!(f, "send target {target:?} is not supported")?
+      }
+      ScxmlErrorKind::InvalidData(id) => write!(f, "invalid data {id:?}")?,
+    }
+    match self.line {
+      0 => Ok(()),
+      line => write!(f, " at {line}:{}", self.column),
+    }
+  }
+}
+impl Error for ScxmlError {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Position {
+  line: usize,
+  column: usize,
+}
+impl Position {
+  fn error(self, kind: ScxmlErrorKind) -> ScxmlError {
+    ScxmlError {
+      line: self.line,
+      column: self.column,
+      kind,
+    }
+  }
+}
+
+fn export_error(kind: ScxmlErrorKind) -> ScxmlError {
+  Position { line: 0, column: 0 }.error(kind)
+}
+
+fn escape(value: &str) -> String {
+  value
+    .replace('&', "&amp;")
+    .replace('<', "&lt;")
+    .replace('>', "&gt;")
+    .replace('"', "&quot;")
+}
+
+/// Exported state with its transitions as `event -> (target, output)`
+#[derive(Default)]
+struct Node {
+  accepting: bool,
+  transitions: BTreeMap<String, (String, Option<String>)>,
+}
+
+fn is_name(name: &str) -> bool {
+  !(name.is_empty() || name.contains(char::is_whitespace) || name.contains('*'))
+}
+
+/// SCXML document with states and their transitions sorted by id
+fn render<'a, State: Hash + Eq + 'a>(
+  initial: &'a State,
+  states: impl IntoIterator<Item = &'a State>,
+  accepting: impl Fn(&State) -> bool,
+  transitions: impl IntoIterator<Item = (&'a State, String, &'a State, Option<String>)>,
+  state: impl Fn(&State) -> String,
+) -> Result<String, ScxmlError> {
+  let mut ids: HashMap<&State, String> = HashMap::new();
+  let mut nodes: BTreeMap<String, Node> = BTreeMap::new();
+  for s in [initial].into_iter().chain(states) {
+    if let Entry::Vacant(entry) = ids.entry(s) {
+      let id = state(s);
+      if id.is_empty() || id.contains(char::is_whitespace) {
+        return Err(export_error(ScxmlErrorKind::InvalidState(id)));
+      }
+      let node = Node {
+        accepting: accepting(s),
+        ..Node::default()
+      };
+      if nodes.insert(id.clone(), node).is_some() {
+        return Err(export_error(ScxmlErrorKind::DuplicateState(id)));
+      }
+      entry.insert(id);
+    }
+  }
+  for (from, event, to, output) in transitions {
+    if !is_name(&event) {
+      return Err(export_error(ScxmlErrorKind::InvalidEvent(event)));
+    }
+    if let Some(output) = output.as_ref().filter(|output| !is_name(output)) {
+      return Err(export_error(ScxmlErrorKind::InvalidOutput(output.clone())));
+    }
+    let node = nodes.get_mut(&ids[from]).unwrap();
+    if node
+      .transitions
+      .insert(event.clone(), (ids[to].clone(), output))
+      .is_some()
+    {
+      return Err(export_error(ScxmlErrorKind::DuplicateTransition {
+        state: ids[from].clone(),
+        event,
+      }));
+    }
+  }
+
+  // data ids share the document-wide id space with the states
+  for (id, node) in &nodes {
+    let data = format!("{id}{ACCEPTING}");
+    if node.accepting && !node.transitions.is_empty() && nodes.contains_key(&data) {
+      return Err(export_error(ScxmlErrorKind::DuplicateState(data)));
+    }
+  }
+
+  let mut scxml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+  writeln!(
+    scxml,
+    "<scxml xmlns=\"{NAMESPACE}\" version=\"1.0\" initial=\"{}\">",
+    escape(&ids[initial])
+  )
+  .unwrap();
+  for (id, node) in &nodes {
+    match (node.accepting, node.transitions.is_empty()) {
+      (true, true) => writeln!(scxml, "  <final id=\"{}\"/>", escape(id)).unwrap(),
+      (false, true) => writeln!(scxml, "  <state id=\"{}\"/>", escape(id)).unwrap(),
+      (_, false) => {
+        writeln!(scxml, "  <state id=\"{}\">", escape(id)).unwrap();
+        if node.accepting {
+          writeln!(
+            scxml,
+            "    <datamodel>\n      <data id=\"{}{ACCEPTING}\" expr=\"true\"/>\n    </datamodel>",
+            escape(id)
+          )
+          .unwrap();
+        }
+        for (event, (target, output)) in &node.transitions {
+          let (event, target) = (escape(event), escape(target));
+          match output {
+            None => writeln!(
+              scxml,
+              "    <transition event=\"{event}\" target=\"{target}\"/>"
+            ),
+            Some(output) => writeln!(
+              scxml,
+              "    <transition event=\"{event}\" target=\"{target}\">\n      \
+               <send event=\"{}\" target=\"{PARENT}\"/>\n    </transition>",
+              escape(output)
+            ),
+          }
+          .unwrap();
+        }
+        scxml.push_str("  </state>\n");
+      }
+    }
+  }
+  scxml.push_str("</scxml>\n");
+  Ok(scxml)
+}
+
+impl<State, Symbol> Dfa<State, Symbol>
+where
+  State: Hash + Eq,
+{
+  pub fn to_scxml(&self) -> Result<String, ScxmlError>
+  where
+    State: Display,
+    Symbol: Display,
+  {
+    self.to_scxml_with(ToString::to_string, ToString::to_string)
+  }
+
+  /// SCXML document with states and their transitions sorted by id
+  ///
+  /// Fails if ids collide or events contain whitespace.
+  pub fn to_scxml_with(
+    &self,
+    state: impl Fn(&State) -> String,
+    symbol: impl Fn(&Symbol) -> String,
+  ) -> Result<String, ScxmlError> {
+    render(
+      self.initial(),
+      self
+        .transitions()
+        .iter()
+        .flat_map(|((from, _), to)| [from, to])
+        .chain(self.accepting()),
+      |s| self.accepting().contains(s),
+      self
+        .transitions()
+        .iter()
+        .map(|((from, s), to)| (from, symbol(s), to, None)),
+      state,
+    )
+  }
+}
+
+/// SCXML document of a transition table as used by [`DriverTransitionTable`](crate::DriverTransitionTable)
+pub fn transition_table_to_scxml<State, Input, Output>(
+  tt: &HashMap<(State, Input), (State, Output)>,
+  initial: &State,
+) -> Result<String, ScxmlError>
+where
+  State: Display + Hash + Eq,
+  Input: Display,
+  Output: Display,
+{
+  transition_table_to_scxml_with(
+    tt,
+    initial,
+    ToString::to_string,
+    ToString::to_string,
+    ToString::to_string,
+  )
+}
+
+/// SCXML document of a transition table with custom ids, events and outputs
+///
+/// Fails if ids collide, or events or outputs contain whitespace.
+pub fn transition_table_to_scxml_with<State, Input, Output>(
+  tt: &HashMap<(State, Input), (State, Output)>,
+  initial: &State,
+  state: impl Fn(&State) -> String,
+  input: impl Fn(&Input) -> String,
+  output: impl Fn(&Output) -> String,
+) -> Result<String, ScxmlError>
+where
+  State: Hash + Eq,
+{
+  render(
+    initial,
+    tt.iter().flat_map(|((from, _), (to, _))| [from, to]),
+    |_| false,
+    tt.iter()
+      .map(|((from, i), (to, o))| (from, input(i), to, Some(output(o)))),
+    state,
+  )
+}
+
+impl<State, Input, Output> Mealy<State, Input, Output>
+where
+  State: Hash + Eq,
+{
+  pub fn to_scxml(&self) -> Result<String, ScxmlError>
+  where
+    State: Display,
+    Input: Display,
+    Output: Display,
+  {
+    transition_table_to_scxml(self.table(), self.initial())
+  }
+
+  pub fn to_scxml_with(
+    &self,
+    state: impl Fn(&State) -> String,
+    input: impl Fn(&Input) -> String,
+    output: impl Fn(&Output) -> String,
+  ) -> Result<String, ScxmlError> {
+    transition_table_to_scxml_with(self.table(), self.initial(), state, input, output)
+  }
+}
+
+/// Element with its attributes in document order
+struct Element {
+  name: String,
+  position: Position,
+  attributes: Vec<(String, String, Position)>,
+  children: Vec<Element>,
+}
+impl Element {
+  fn attribute(&self, name: &str) -> Option<&(String, String, Position)> {
+    self.attributes.iter().find(|(key, _, _)| key == name)
+  }
+
+  /// Rejects attributes other than `allowed` and namespace declarations
+  fn check_attributes(&self, allowed: &[&str]) -> Result<(), ScxmlError> {
+    for (name, _, position) in &self.attributes {
+      if !(allowed.contains(&name.as_str()) || name == "xmlns" || name.starts_with("xmlns:")) {
+        return Err(position.error(ScxmlErrorKind::UnsupportedAttribute(name.clone())));
+      }
+    }
+    Ok(())
+  }
+
+  fn unsupported(&self) -> ScxmlError {
+    let kind = ScxmlErrorKind::UnsupportedElement(self.name.clone());
+    self.position.error(kind)
+  }
+
+  fn required(&self, name: &'static str) -> Result<(&str, Position), ScxmlError> {
+    match self.attribute(name) {
+      Some((_, value, position)) => Ok((value, *position)),
+      None => Err(self.position.error(ScxmlErrorKind::MissingAttribute(name))),
+    }
+  }
+}
+
+/// Checks that a `<datamodel>` holds only the `<data>` marking the state `id` as accepting
+fn accepting_marker(datamodel: &Element, id: &str) -> Result<(), ScxmlError> {
+  datamodel.check_attributes(&[])?;
+  let data = match datamodel.children.as_slice() {
+    [data] if local_name(&data.name) == "data" => data,
+    [data, extra, ..] if local_name(&data.name) == "data" => return Err(extra.unsupported()),
+    [other, ..] => return Err(other.unsupported()),
+    [] => return Err(datamodel.unsupported()),
+  };
+  data.check_attributes(&["id", "expr"])?;
+  if let Some(child) = data.children.first() {
+    return Err(child.unsupported());
+  }
+  let (data_id, position) = data.required("id")?;
+  if data_id != format!("{id}{ACCEPTING}") || data.required("expr")?.0 != "true" {
+    return Err(position.error(ScxmlErrorKind::InvalidData(data_id.into())));
+  }
+  Ok(())
+}
+
+/// Minimal XML reader for elements, attributes, comments and the XML declaration
+struct Reader<'a> {
+  chars: Peekable<Chars<'a>>,
+  position: Position,
+}
+impl Reader<'_> {
+  fn bump(&mut self) -> Result<char, ScxmlError> {
+    let c = self
+      .chars
+      .next()
+      .ok_or(self.position.error(ScxmlErrorKind::UnexpectedEnd))?;
+    match c {
+      '\n' => {
+        self.position.line += 1;
+        self.position.column = 1;
+      }
+      _ => self.position.column += 1,
+    }
+    Ok(c)
+  }
+
+  fn eat(&mut self, expected: &str) -> Result<bool, ScxmlError> {
+    let mut lookahead = self.chars.clone();
+    if !expected.chars().all(|c| lookahead.next() == Some(c)) {
+      return Ok(false);
+    }
+    for _ in expected.chars() {
+      self.bump()?;
+    }
+    Ok(true)
+  }
+
+  fn expect(&mut self, expected: char) -> Result<(), ScxmlError> {
+    let position = self.position;
+    match self.bump()? {
+      c if c == expected => Ok(()),
+      c => Err(position.error(ScxmlErrorKind::Unexpected(c))),
+    }
+  }
+
+  fn skip_until(&mut self, end: &str) -> Result<(), ScxmlError> {
+    while !self.eat(end)? {
+      self.bump()?;
+    }
+    Ok(())
+  }
+
+  fn skip_whitespace(&mut self) -> Result<(), ScxmlError> {
+    while self.chars.peek().is_some_and(|c| c.is_whitespace()) {
+      self.bump()?;
+    }
+    Ok(())
+  }
+
+  /// Whitespace, comments and processing instructions
+  fn skip_misc(&mut self) -> Result<(), ScxmlError> {
+    loop {
+      self.skip_whitespace()?;
+      if self.eat("<!--")? {
+        self.skip_until("-->")?;
+      } else if self.eat("<?")? {
+        self.skip_until("?>")?;
+      } else {
+        return Ok(());
+      }
+    }
+  }
+
+  fn name(&mut self) -> Result<String, ScxmlError> {
+    let mut name = String::new();
+    while let Some(&c) = self.chars.peek() {
+      if !(c.is_alphanumeric() || matches!(c, '_' | '-' | '.' | ':')) {
+        break;
+      }
+      name.push(self.bump()?);
+    }
+    match name.is_empty() {
+      true => {
+        let position = self.position;
+        Err(position.error(ScxmlErrorKind::Unexpected(self.bump()?)))
+      }
+      false => Ok(name),
+    }
+  }
+
+  fn entity(&mut self) -> Result<char, ScxmlError> {
+    let position = self.position;
+    let mut entity = String::new();
+    loop {
+      match self.bump()? {
+        ';' => break,
+        c => entity.push(c),
+      }
+    }
+    let code = match entity.strip_prefix("#x") {
+      Some(hex) => u32::from_str_radix(hex, 16).ok(),
+      None => entity
+        .strip_prefix('#')
+        .and_then(|decimal| decimal.parse().ok()),
+    };
+    match entity.as_str() {
+      "lt" => Ok('<'),
+      "gt" => Ok('>'),
+      "amp" => Ok('&'),
+      "quot" => Ok('"'),
+      "apos" => Ok('\''),
+      _ => code
+        .and_then(char::from_u32)
+        .ok_or(position.error(ScxmlErrorKind::UnknownEntity(entity))),
+    }
+  }
+
+  /// Element starting after its `<`
+  fn element(&mut self, position: Position) -> Result<Element, ScxmlError> {
+    let mut element = Element {
+      name: self.name()?,
+      position,
+      attributes: Vec::new(),
+      children: Vec::new(),
+    };
+    loop {
+      self.skip_whitespace()?;
+      if self.eat("/>")? {
+        return Ok(element);
+      }
+      if self.eat(">")? {
+        break;
+      }
+      let position = self.position;
+      let name = self.name()?;
+      self.skip_whitespace()?;
+      self.expect('=')?;
+      self.skip_whitespace()?;
+      let quote_position = self.position;
+      let quote = match self.bump()? {
+        quote @ ('"' | '\'') => quote,
+        c => return Err(quote_position.error(ScxmlErrorKind::Unexpected(c))),
+      };
+      let mut value = String::new();
+      loop {
+        let character_position = self.position;
+        match self.bump()? {
+          c if c == quote => break,
+          '&' => value.push(self.entity()?),
+          '<' => return Err(character_position.error(ScxmlErrorKind::Unexpected('<'))),
+          c => value.push(c),
+        }
+      }
+      if element.attribute(&name).is_some() {
+        return Err(position.error(ScxmlErrorKind::DuplicateAttribute(name)));
+      }
+      element.attributes.push((name, value, position));
+    }
+
+    loop {
+      self.skip_whitespace()?;
+      let position = self.position;
+      if self.eat("<!--")? {
+        self.skip_until("-->")?;
+      } else if self.eat("</")? {
+        let name = self.name()?;
+        self.skip_whitespace()?;
+        self.expect('>')?;
+        if name != element.name {
+          return Err(position.error(ScxmlErrorKind::Mismatched {
+            expected: element.name,
+            found: name,
+          }));
+        }
+        return Ok(element);
+      } else if self.eat("<![CDATA[")? {
+        return Err(position.error(ScxmlErrorKind::Text));
+      } else if self.eat("<")? {
+        element.children.push(self.element(position)?);
+      } else {
+        self.bump()?;
+        return Err(position.error(ScxmlErrorKind::Text));
+      }
+    }
+  }
+
+  fn document(&mut self) -> Result<Element, ScxmlError> {
+    self.skip_misc()?;
+    let position = self.position;
+    if self.eat("<!")? {
+      return Err(position.error(ScxmlErrorKind::UnsupportedElement("!DOCTYPE".into())));
+    }
+    self.expect('<')?;
+    let root = self.element(position)?;
+    self.skip_misc()?;
+    match self.chars.peek() {
+      None => Ok(root),
+      Some(_) => {
+        let position = self.position;
+        Err(position.error(ScxmlErrorKind::Unexpected(self.bump()?)))
+      }
+    }
+  }
+}
+
+/// Drops a namespace prefix, SCXML elements may be written as `<sc:state>`
+fn local_name(name: &str) -> &str {
+  name.rsplit(':').next().unwrap()
+}
+
+/// Initial state, accepting states and transition table of a flat SCXML document
+struct Parsed<State, Event, Output> {
+  initial: State,
+  /// Accepting states in document order, with the rejection of their `<final>` or `<datamodel>`
+  /// for machines without acceptance
+  accepting: Vec<(State, ScxmlError)>,
+  #[allow(clippy::type_complexity)]
+  table: HashMap<(State, Event), (State, Output)>,
+}
+
+/// Parses a document, reading the output of every transition and event with `output`
+fn parse_document<State, Event, Output>(
+  scxml: &str,
+  output: impl Fn(&Element) -> Result<Output, ScxmlError>,
+) -> Result<Parsed<State, Event, Output>, ScxmlError>
+where
+  State: FromStr + Hash + Eq + Clone,
+  Event: FromStr + Hash + Eq,
+{
+  let root = Reader {
+    chars: scxml.chars().peekable(),
+    position: Position { line: 1, column: 1 },
+  }
+  .document()?;
+  if local_name(&root.name) != "scxml" {
+    return Err(root.unsupported());
+  }
+  root.check_attributes(&["version", "initial", "name"])?;
+
+  let mut states = HashMap::new();
+  for element in &root.children {
+    let (id, position) = match local_name(&element.name) {
+      "state" | "final" => element.required("id")?,
+      _ => return Err(element.unsupported()),
+    };
+    let state = id
+      .parse::<State>()
+      .map_err(|_| position.error(ScxmlErrorKind::InvalidState(id.into())))?;
+    if states.insert(id, state).is_some() {
+      return Err(position.error(ScxmlErrorKind::DuplicateState(id.into())));
+    }
+  }
+  let state = |(id, position): (&str, Position)| {
+    states
+      .get(id)
+      .cloned()
+      .ok_or(position.error(ScxmlErrorKind::UnknownState(id.into())))
+  };
+
+  let initial = match root.attribute("initial") {
+    Some((_, id, position)) => state((id, *position))?,
+    None => match root.children.first() {
+      Some(first) => state(first.required("id")?)?,
+      None => {
+        return Err(
+          root
+            .position
+            .error(ScxmlErrorKind::MissingAttribute("initial")),
+        )
+      }
+    },
+  };
+  let mut table = HashMap::new();
+  let mut accepting = Vec::new();
+  for element in &root.children {
+    let id = element.required("id")?;
+    let from = state(id)?;
+    element.check_attributes(&["id"])?;
+    if local_name(&element.name) == "final" {
+      if let Some(child) = element.children.first() {
+        return Err(child.unsupported());
+      }
+      accepting.push((from, element.unsupported()));
+      continue;
+    }
+
+    for (index, transition) in element.children.iter().enumerate() {
+      match local_name(&transition.name) {
+        "transition" => {}
+        "datamodel" if index == 0 => {
+          accepting_marker(transition, id.0)?;
+          accepting.push((from.clone(), transition.unsupported()));
+          continue;
+        }
+        _ => return Err(transition.unsupported()),
+      }
+      transition.check_attributes(&["event", "target"])?;
+      let (events, events_position) = transition.required("event")?;
+      let (target, target_position) = transition.required("target")?;
+      if target.split_whitespace().count() != 1 {
+        let kind = ScxmlErrorKind::UnknownState(target.into());
+        return Err(target_position.error(kind));
+      }
+      let to = state((target, target_position))?;
+      for event in events.split_whitespace() {
+        if event.contains('*') || event.ends_with('.') {
+          let kind = ScxmlErrorKind::UnsupportedEvent(event.into());
+          return Err(events_position.error(kind));
+        }
+        let symbol = event
+          .parse::<Event>()
+          .map_err(|_| events_position.error(ScxmlErrorKind::InvalidEvent(event.into())))?;
+        match table.entry((from.clone(), symbol)) {
+          Entry::Occupied(_) => {
+            return Err(events_position.error(ScxmlErrorKind::DuplicateTransition {
+              state: id.0.into(),
+              event: event.into(),
+            }));
+          }
+          Entry::Vacant(entry) => {
+            entry.insert((to.clone(), output(transition)?));
+          }
+        }
+      }
+    }
+  }
+  Ok(Parsed {
+    initial,
+    accepting,
+    table,
+  })
+}
+
+impl<State, Symbol> Dfa<State, Symbol>
+where
+  State: FromStr + Hash + Eq + Clone,
+  Symbol: FromStr + Hash + Eq,
+{
+  /// Acceptor from the flat subset of SCXML
+  ///
+  /// Without an `initial` attribute the first state in document order is initial.
+  /// A transition may list several events separated by whitespace.
+  pub fn from_scxml(scxml: &str) -> Result<Self, ScxmlError> {
+    let parsed = parse_document(scxml, |transition| match transition.children.first() {
+      Some(child) => Err(child.unsupported()),
+      None => Ok(()),
+    })?;
+    let transitions = parsed
+      .table
+      .into_iter()
+      .map(|(key, (to, ()))| (key, to))
+      .collect();
+    let accepting = parsed.accepting.into_iter().map(|(state, _)| state);
+    Ok(Dfa::new(parsed.initial, transitions, accepting.collect()))
+  }
+}
+
+impl<State, Input, Output> Mealy<State, Input, Output>
+where
+  State: FromStr + Hash + Eq + Clone,
+  Input: FromStr + Hash + Eq,
+  Output: FromStr,
+{
+  /// Mealy machine from the flat subset of SCXML
+  ///
+  /// Like [`Dfa::from_scxml`], but every transition sends its output to `#_parent`
+  /// and there are no accepting states.
+  pub fn from_scxml(scxml: &str) -> Result<Self, ScxmlError> {
+    let parsed = parse_document(scxml, |transition| {
+      let mut children = transition.children.iter();
+      let send = match children.next() {
+        Some(send) if local_name(&send.name) == "send" => send,
+        Some(other) => return Err(other.unsupported()),
+        None => return Err(transition.position.error(ScxmlErrorKind::MissingOutput)),
+      };
+      if let Some(other) = children.next().or(send.children.first()) {
+        return Err(other.unsupported());
+      }
+      send.check_attributes(&["event", "target"])?;
+      let (target, position) = send.required("target")?;
+      if target != PARENT {
+        return Err(position.error(ScxmlErrorKind::UnsupportedTarget(target.into())));
+      }
+      let (event, position) = send.required("event")?;
+      event
+        .parse::<Output>()
+        .map_err(|_| position.error(ScxmlErrorKind::InvalidOutput(event.into())))
+    })?;
+    if let Some((_, error)) = parsed.accepting.into_iter().next() {
+      return Err(error);
+    }
+    Ok(Mealy::new(parsed.initial, parsed.table))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{equivalent, Regex};
+  use std::collections::HashSet;
+
+  #[test]
+  fn turnstile_round_trip() {
+    let mealy = Mealy::new(
+      "Locked".to_string(),
+      HashMap::from([
+        (
+          ("Locked".into(), "push".to_string()),
+          ("Locked".into(), "blocked".to_string()),
+        ),
+        (
+          ("Locked".into(), "coin".into()),
+          ("Unlocked".into(), "unlock".into()),
+        ),
+        (
+          ("Unlocked".into(), "coin".into()),
+          ("Unlocked".into(), "refund".into()),
+        ),
+        (
+          ("Unlocked".into(), "push".into()),
+          ("Locked".into(), "lock".into()),
+        ),
+      ]),
+    );
+    let scxml = mealy.to_scxml().unwrap();
+    assert_eq!(
+      scxml,
+      r##"<?xml version="1.0" encoding="UTF-8"?>
+<scxml xmlns="http://www.w3.org/2005/07/scxml" version="1.0" initial="Locked">
+  <state id="Locked">
+    <transition event="coin" target="Unlocked">
+      <send event="unlock" target="#_parent"/>
+    </transition>
+    <transition event="push" target="Locked">
+      <send event="blocked" target="#_parent"/>
+    </transition>
+  </state>
+  <state id="Unlocked">
+    <transition event="coin" target="Unlocked">
+      <send event="refund" target="#_parent"/>
+    </transition>
+    <transition event="push" target="Locked">
+      <send event="lock" target="#_parent"/>
+    </transition>
+  </state>
+</scxml>
+"##
+    );
+    let copy = Mealy::<String, String, String>::from_scxml(&scxml).unwrap();
+    assert_eq!(copy.initial(), mealy.initial());
+    assert_eq!(copy.table(), mealy.table());
+
+    // the same turnstile as acceptor of the words ending unlocked
+    let transitions = mealy
+      .table()
+      .iter()
+      .map(|(key, (to, _))| (key.clone(), to.clone()))
+      .collect();
+    let dfa = Dfa::new(
+      "Locked".to_string(),
+      transitions,
+      HashSet::from(["Unlocked".to_string()]),
+    );
+    let scxml = dfa.to_scxml().unwrap();
+    assert!(scxml.contains(concat!(
+      "  <state id=\"Unlocked\">\n    <datamodel>\n",
+      "      <data id=\"Unlocked.accepting\" expr=\"true\"/>\n    </datamodel>\n"
+    )));
+    let copy = Dfa::<String, String>::from_scxml(&scxml).unwrap();
+    assert_eq!(copy.initial(), dfa.initial());
+    assert_eq!(copy.transitions(), dfa.transitions());
+    assert_eq!(copy.accepting(), dfa.accepting());
+
+    let scxml = r#"
+      <!-- hand written -->
+      <scxml xmlns="http://www.w3.org/2005/07/scxml" version='1.0'>
+        <state id="0"><transition event="a b" target="1" /></state>
+        <final id="1"></final>
+      </scxml>"#;
+    let dfa = Dfa::<u8, char>::from_scxml(scxml).unwrap();
+    assert_eq!(dfa.initial(), &0);
+    assert_eq!(dfa.transitions().len(), 2);
+    assert_eq!(dfa.accepting(), &HashSet::from([1]));
+  }
+
+  #[test]
+  fn minimized_round_trip() {
+    let dfa = Regex::<char>::parse("(a|b)*abb").unwrap().to_dfa();
+    let copy = Dfa::<usize, char>::from_scxml(&dfa.to_scxml().unwrap()).unwrap();
+    assert_eq!(equivalent(&copy, &dfa), Ok(()));
+  }
+
+  #[test]
+  fn diagnostics() {
+    let error = |scxml: &str| Dfa::<u8, char>::from_scxml(scxml).unwrap_err();
+    assert_eq!(
+      error("<scxml>\n  <parallel id=\"0\"/>\n</scxml>").to_string(),
+      "<parallel> is not supported at 2:3"
+    );
+    assert_eq!(
+      error(
+        "<scxml><state id=\"0\"><transition event=\"a\" target=\"0\" cond=\"x\"/></state></scxml>"
+      )
+      .kind,
+      ScxmlErrorKind::UnsupportedAttribute("cond".into())
+    );
+    assert_eq!(
+      error(
+        "<scxml><state id=\"0\"><transition event=\"a\" target=\"0\" type=\"internal\"/></state></scxml>"
+      )
+      .kind,
+      ScxmlErrorKind::UnsupportedAttribute("type".into())
+    );
+    assert_eq!(
+      error("<scxml><state id=\"0\"><onentry/></state></scxml>").kind,
+      ScxmlErrorKind::UnsupportedElement("onentry".into())
+    );
+    // a <final> child would make the state compound
+    assert_eq!(
+      error("<scxml><state id=\"0\"><final id=\"1\"/></state></scxml>").kind,
+      ScxmlErrorKind::UnsupportedElement("final".into())
+    );
+    let marked =
+      |data: &str| format!("<scxml><state id=\"0\"><datamodel>{data}</datamodel></state></scxml>");
+    assert_eq!(
+      error(&marked("<data id=\"1.accepting\" expr=\"true\"/>")).kind,
+      ScxmlErrorKind::InvalidData("1.accepting".into())
+    );
+    assert_eq!(
+      error(&marked("<data id=\"0.accepting\" expr=\"false\"/>")).kind,
+      ScxmlErrorKind::InvalidData("0.accepting".into())
+    );
+    assert_eq!(
+      error(&marked(
+        "<data id=\"0.accepting\" expr=\"true\"/><data id=\"x\"/>"
+      ))
+      .kind,
+      ScxmlErrorKind::UnsupportedElement("data".into())
+    );
+    let dfa = Dfa::<u8, char>::from_scxml(&marked("<data id=\"0.accepting\" expr=\"true\"/>"));
+    assert_eq!(dfa.unwrap().accepting(), &HashSet::from([0]));
+    assert_eq!(
+      error("<scxml><state id=\"0\"><transition target=\"0\"/></state></scxml>").kind,
+      ScxmlErrorKind::MissingAttribute("event")
+    );
+    assert_eq!(
+      error("<scxml><state id=\"0\"><transition event=\"a\" target=\"1\"/></state></scxml>").kind,
+      ScxmlErrorKind::UnknownState("1".into())
+    );
+    assert_eq!(
+      error("<scxml><state id=\"0\"><transition event=\"*\" target=\"0\"/></state></scxml>").kind,
+      ScxmlErrorKind::UnsupportedEvent("*".into())
+    );
+    assert_eq!(
+      error("<scxml><state id=\"0\"><transition event=\"a a\" target=\"0\"/></state></scxml>").kind,
+      ScxmlErrorKind::DuplicateTransition {
+        state: "0".into(),
+        event: "a".into()
+      }
+    );
+    assert_eq!(
+      error("<scxml><state id=\"x\"/></scxml>").kind,
+      ScxmlErrorKind::InvalidState("x".into())
+    );
+    assert_eq!(
+      error("<scxml><state id=\"0\">text</state></scxml>").kind,
+      ScxmlErrorKind::Text
+    );
+    assert_eq!(
+      error("<scxml><state id=\"0\"></final></scxml>").kind,
+      ScxmlErrorKind::Mismatched {
+        expected: "state".into(),
+        found: "final".into()
+      }
+    );
+    assert_eq!(error("<scxml>").kind, ScxmlErrorKind::UnexpectedEnd);
+    // outputs are not dropped from acceptors
+    assert_eq!(
+      error(concat!(
+        "<scxml><state id=\"0\"><transition event=\"a\" target=\"0\">",
+        "<send event=\"b\" target=\"#_parent\"/></transition></state></scxml>"
+      ))
+      .kind,
+      ScxmlErrorKind::UnsupportedElement("send".into())
+    );
+
+    let error = |scxml: &str| Mealy::<u8, char, char>::from_scxml(scxml).unwrap_err();
+    let transition = |send: &str| {
+      format!("<scxml><state id=\"0\"><transition event=\"a\" target=\"0\">{send}</transition></state></scxml>")
+    };
+    assert_eq!(error(&transition("")).kind, ScxmlErrorKind::MissingOutput);
+    assert_eq!(
+      error(&transition("<send event=\"b\" target=\"#x\"/>")).kind,
+      ScxmlErrorKind::UnsupportedTarget("#x".into())
+    );
+    assert_eq!(
+      error(&transition("<send event=\"bc\" target=\"#_parent\"/>")).kind,
+      ScxmlErrorKind::InvalidOutput("bc".into())
+    );
+    assert_eq!(
+      error("<scxml><final id=\"0\"/></scxml>").kind,
+      ScxmlErrorKind::UnsupportedElement("final".into())
+    );
+    assert_eq!(
+      error(&marked("<data id=\"0.accepting\" expr=\"true\"/>")).kind,
+      ScxmlErrorKind::UnsupportedElement("datamodel".into())
+    );
+
+    let dfa = Dfa::new(0, HashMap::from([((0, ' '), 0)]), HashSet::from([0]));
+    assert_eq!(
+      dfa.to_scxml().unwrap_err().kind,
+      ScxmlErrorKind::InvalidEvent(" ".into())
+    );
+    let dfa = Dfa::new(
+      "a".to_string(),
+      HashMap::from([(("a".into(), 'x'), "a.accepting".into())]),
+      HashSet::from(["a".into()]),
+    );
+    assert_eq!(
+      dfa.to_scxml().unwrap_err().kind,
+      ScxmlErrorKind::DuplicateState("a.accepting".into())
+    );
+    let mealy = Mealy::new(0, HashMap::from([((0, 'a'), (0, "two words"))]));
+    assert_eq!(
+      mealy.to_scxml().unwrap_err().kind,
+      ScxmlErrorKind::InvalidOutput("two words".into())
+    );
+  }
+}
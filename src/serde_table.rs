@@ -0,0 +1,348 @@
+//! Serde layouts for transition tables
+//!
+//! Tables keyed by tuples have no JSON or TOML representation, so they are written as
+//! a list of transitions instead, e.g. `[{"from": 0, "input": "coin", "to": 1, "output": "unlock"}]`.
+//! The order of the list is unspecified, deserialization rejects duplicate keys.
+//!
+//! The module itself serializes tables for [`DriverTransitionTable`](crate::DriverTransitionTable)
+//! and is meant for `#[serde(with = "automaton::serde_table")]`, the submodules cover the other tables.
+
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+use std::{
+  collections::{hash_map::Entry, HashMap, HashSet},
+  hash::Hash,
+};
+
+/// Transducer transition
+#[derive(Serialize, Deserialize)]
+pub struct Transition<State, Input, Output> {
+  pub from: State,
+  pub input: Input,
+  pub to: State,
+  pub output: Output,
+}
+
+pub fn serialize<State, Input, Output, S>(
+  table: &HashMap<(State, Input), (State, Output)>,
+  serializer: S,
+) -> Result<S::Ok, S::Error>
+where
+  State: Serialize,
+  Input: Serialize,
+  Output: Serialize,
+  S: Serializer,
+{
+  serializer.collect_seq(
+    table
+      .iter()
+      .map(|((from, input), (to, output))| Transition {
+        from,
+        input,
+        to,
+        output,
+      }),
+  )
+}
+
+#[allow(clippy::type_complexity)]
+pub fn deserialize<'de, State, Input, Output, D>(
+  deserializer: D,
+) -> Result<HashMap<(State, Input), (State, Output)>, D::Error>
+where
+  State: Deserialize<'de> + Hash + Eq,
+  Input: Deserialize<'de> + Hash + Eq,
+  Output: Deserialize<'de>,
+  D: Deserializer<'de>,
+{
+  let transitions = Vec::<Transition<State, Input, Output>>::deserialize(deserializer)?;
+  collect(transitions.into_iter().map(|transition| {
+    (
+      (transition.from, transition.input),
+      (transition.to, transition.output),
+    )
+  }))
+}
+
+fn collect<Key, Value, E>(
+  entries: impl Iterator<Item = (Key, Value)>,
+) -> Result<HashMap<Key, Value>, E>
+where
+  Key: Hash + Eq,
+  E: de::Error,
+{
+  let mut table = HashMap::new();
+  for (key, value) in entries {
+    match table.entry(key) {
+      Entry::Occupied(_) => return Err(E::custom("duplicate transition")),
+      Entry::Vacant(entry) => {
+        entry.insert(value);
+      }
+    }
+  }
+  Ok(table)
+}
+
+/// Acceptor transitions `{from, symbol, to}` of a [`Dfa`](crate::Dfa)
+pub mod acceptor {
+  use super::*;
+
+  #[derive(Serialize, Deserialize)]
+  pub struct Transition<State, Symbol> {
+    pub from: State,
+    pub symbol: Symbol,
+    pub to: State,
+  }
+
+  pub fn serialize<State, Symbol, S>(
+    transitions: &HashMap<(State, Symbol), State>,
+    serializer: S,
+  ) -> Result<S::Ok, S::Error>
+  where
+    State: Serialize,
+    Symbol: Serialize,
+    S: Serializer,
+  {
+    serializer.collect_seq(transitions.iter().map(|((from, symbol), to)| Transition {
+      from,
+      symbol,
+      to,
+    }))
+  }
+
+  pub fn deserialize<'de, State, Symbol, D>(
+    deserializer: D,
+  ) -> Result<HashMap<(State, Symbol), State>, D::Error>
+  where
+    State: Deserialize<'de> + Hash + Eq,
+    Symbol: Deserialize<'de> + Hash + Eq,
+    D: Deserializer<'de>,
+  {
+    let transitions = Vec::<Transition<State, Symbol>>::deserialize(deserializer)?;
+    collect(
+      transitions
+        .into_iter()
+        .map(|transition| ((transition.from, transition.symbol), transition.to)),
+    )
+  }
+}
+
+/// Nondeterministic transitions `{from, symbol, to}` of an [`Nfa`](crate::Nfa), one per target
+pub mod nondeterministic {
+  use super::{acceptor::Transition, *};
+
+  pub fn serialize<State, Symbol, S>(
+    transitions: &HashMap<(State, Symbol), HashSet<State>>,
+    serializer: S,
+  ) -> Result<S::Ok, S::Error>
+  where
+    State: Serialize,
+    Symbol: Serialize,
+    S: Serializer,
+  {
+    serializer.collect_seq(
+      transitions
+        .iter()
+        .flat_map(|((from, symbol), to)| to.iter().map(move |to| Transition { from, symbol, to })),
+    )
+  }
+
+  #[allow(clippy::type_complexity)]
+  pub fn deserialize<'de, State, Symbol, D>(
+    deserializer: D,
+  ) -> Result<HashMap<(State, Symbol), HashSet<State>>, D::Error>
+  where
+    State: Deserialize<'de> + Hash + Eq,
+    Symbol: Deserialize<'de> + Hash + Eq,
+    D: Deserializer<'de>,
+  {
+    let mut table: HashMap<_, HashSet<_>> = HashMap::new();
+    for transition in Vec::<Transition<State, Symbol>>::deserialize(deserializer)? {
+      table
+        .entry((transition.from, transition.symbol))
+        .or_default()
+        .insert(transition.to);
+    }
+    Ok(table)
+  }
+}
+
+/// Epsilon moves `{from, to}` of an [`Nfa`](crate::Nfa), one per target
+pub mod epsilon {
+  use super::*;
+
+  #[derive(Serialize, Deserialize)]
+  pub struct Move<State> {
+    pub from: State,
+    pub to: State,
+  }
+
+  pub fn serialize<State, S>(
+    epsilon: &HashMap<State, HashSet<State>>,
+    serializer: S,
+  ) -> Result<S::Ok, S::Error>
+  where
+    State: Serialize,
+    S: Serializer,
+  {
+    serializer.collect_seq(
+      epsilon
+        .iter()
+        .flat_map(|(from, to)| to.iter().map(move |to| Move { from, to })),
+    )
+  }
+
+  pub fn deserialize<'de, State, D>(
+    deserializer: D,
+  ) -> Result<HashMap<State, HashSet<State>>, D::Error>
+  where
+    State: Deserialize<'de> + Hash + Eq,
+    D: Deserializer<'de>,
+  {
+    let mut epsilon: HashMap<_, HashSet<_>> = HashMap::new();
+    for Move { from, to } in Vec::<Move<State>>::deserialize(deserializer)? {
+      epsilon.entry(from).or_default().insert(to);
+    }
+    Ok(epsilon)
+  }
+}
+
+/// State outputs `{state, output}` of a [`MooreMachine`](crate::MooreMachine)
+pub mod state_output {
+  use super::*;
+
+  #[derive(Serialize, Deserialize)]
+  pub struct StateOutput<State, Output> {
+    pub state: State,
+    pub output: Output,
+  }
+
+  pub fn serialize<State, Output, S>(
+    outputs: &HashMap<State, Output>,
+    serializer: S,
+  ) -> Result<S::Ok, S::Error>
+  where
+    State: Serialize,
+    Output: Serialize,
+    S: Serializer,
+  {
+    serializer.collect_seq(
+      outputs
+        .iter()
+        .map(|(state, output)| StateOutput { state, output }),
+    )
+  }
+
+  pub fn deserialize<'de, State, Output, D>(
+    deserializer: D,
+  ) -> Result<HashMap<State, Output>, D::Error>
+  where
+    State: Deserialize<'de> + Hash + Eq,
+    Output: Deserialize<'de>,
+    D: Deserializer<'de>,
+  {
+    let outputs = Vec::<StateOutput<State, Output>>::deserialize(deserializer)?;
+    collect(outputs.into_iter().map(|entry| (entry.state, entry.output)))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use crate::{Dfa, Mealy, MooreMachine, Nfa, Regex, StateMachine};
+  use serde::{Deserialize, Serialize};
+  use std::collections::{HashMap, HashSet};
+
+  #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+  enum State {
+    Locked,
+    Unlocked,
+  }
+
+  #[test]
+  fn table_layout() {
+    #[derive(Serialize, Deserialize)]
+    struct Config {
+      #[serde(with = "crate::serde_table")]
+      table: HashMap<(State, String), (State, String)>,
+    }
+    let config = Config {
+      table: HashMap::from([(
+        (State::Locked, "coin".to_string()),
+        (State::Unlocked, "unlock".to_string()),
+      )]),
+    };
+    assert_eq!(
+      serde_json::to_string(&config).unwrap(),
+      r#"{"table":[{"from":"Locked","input":"coin","to":"Unlocked","output":"unlock"}]}"#
+    );
+
+    let toml = r#"
+      [[table]]
+      from = "Locked"
+      input = "push"
+      to = "Locked"
+      output = "blocked"
+
+      [[table]]
+      from = "Locked"
+      input = "coin"
+      to = "Unlocked"
+      output = "unlock"
+    "#;
+    let config: Config = toml::from_str(toml).unwrap();
+    assert_eq!(config.table.len(), 2);
+    assert_eq!(
+      config.table[&(State::Locked, "coin".to_string())],
+      (State::Unlocked, "unlock".to_string())
+    );
+    assert!(toml::from_str::<Config>(&toml.replace("coin", "push")).is_err());
+  }
+
+  #[test]
+  fn automata_round_trip() {
+    let state_machine: StateMachine<State> =
+      serde_json::from_str(&serde_json::to_string(&StateMachine::new(State::Unlocked)).unwrap())
+        .unwrap();
+    assert_eq!(state_machine.state(), &State::Unlocked);
+
+    let mealy = Mealy::new(
+      0u8,
+      HashMap::from([((0, 'a'), (1, true)), ((1, 'a'), (0, false))]),
+    );
+    let json = serde_json::to_string(&mealy).unwrap();
+    let copy: Mealy<u8, char, bool> = serde_json::from_str(&json).unwrap();
+    assert_eq!(
+      (copy.initial(), copy.table()),
+      (mealy.initial(), mealy.table())
+    );
+
+    let dfa = Dfa::new(0u8, HashMap::from([((0, 'a'), 1)]), HashSet::from([1]));
+    let copy: Dfa<u8, char> = toml::from_str(&toml::to_string(&dfa).unwrap()).unwrap();
+    assert_eq!(copy.transitions(), dfa.transitions());
+    assert_eq!(copy.accepting(), dfa.accepting());
+
+    let nfa = Regex::<char>::parse("a(b|c)*").unwrap().thompson();
+    let copy: Nfa<usize, char> =
+      serde_json::from_str(&serde_json::to_string(&nfa).unwrap()).unwrap();
+    assert_eq!(copy.transitions(), nfa.transitions());
+    assert_eq!(copy.epsilon(), nfa.epsilon());
+
+    let moore = MooreMachine::new(
+      State::Locked,
+      HashMap::from([((State::Locked, 'c'), State::Unlocked)]),
+      HashMap::from([(State::Locked, false), (State::Unlocked, true)]),
+    )
+    .unwrap();
+    let json = serde_json::to_string(&moore).unwrap();
+    let copy: MooreMachine<State, char, bool> = serde_json::from_str(&json).unwrap();
+    assert_eq!(copy.transitions(), moore.transitions());
+    assert_eq!(copy.outputs(), moore.outputs());
+    let unchecked = json.replace(r#"{"state":"Unlocked","output":true}"#, "");
+    let unchecked = unchecked.replace(",]", "]").replace("[,", "[");
+    let error = serde_json::from_str::<MooreMachine<State, char, bool>>(&unchecked).unwrap_err();
+    assert_eq!(error.to_string(), "no output for state Unlocked");
+
+    let regex = Regex::<char>::parse("[ab]{2,}|c?").unwrap();
+    let copy: Regex<char> = serde_json::from_str(&serde_json::to_string(&regex).unwrap()).unwrap();
+    assert_eq!(copy, regex);
+  }
+}
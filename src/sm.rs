@@ -1,15 +1,24 @@
-use std::{collections::HashMap, hash::Hash, marker::PhantomData};
+#[cfg(feature = "alloc")]
+use alloc::{collections::BTreeMap, vec::Vec};
+use core::{error::Error, fmt, marker::PhantomData};
+#[cfg(feature = "std")]
+use std::{collections::HashMap, hash::Hash};
 
 /// State Machine
 ///
 /// Actually a finite-state transducer
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct StateMachine<State> {
-  state: State,
+  pub(crate) state: State,
 }
 impl<State> StateMachine<State> {
   pub fn new(state: State) -> Self {
     Self { state }
   }
+
+  pub fn state(&self) -> &State {
+    &self.state
+  }
 }
 
 /// State machine driver
@@ -17,7 +26,34 @@ pub trait Driver<Input, Output> {
   fn step(&mut self, input: Input) -> Output;
 }
 
+/// Fallible state machine driver
+///
+/// On failure the state machine stays in its previous state.
+pub trait TryDriver<Input, Output> {
+  type State;
+  fn try_step(&mut self, input: Input) -> Result<Output, StepError<Self::State, Input>>;
+}
+
+/// Missing transition for a `(State, Input)` pair
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct StepError<State, Input> {
+  pub state: State,
+  pub input: Input,
+}
+impl<State: fmt::Debug, Input: fmt::Debug> fmt::Display for StepError<State, Input> {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(
+      f,
+      "no transition from state {:?} on input {:?}",
+      self.state, self.input
+    )
+  }
+}
+impl<State: fmt::Debug, Input: fmt::Debug> Error for StepError<State, Input> {}
+
 pub trait DriverExt<Input, Output>: Driver<Input, Output> {
+  #[cfg(feature = "alloc")]
   fn run<InputIterator>(&mut self, inputs: InputIterator) -> Vec<Output>
   where
     InputIterator: IntoIterator<Item = Input>,
@@ -28,15 +64,52 @@ pub trait DriverExt<Input, Output>: Driver<Input, Output> {
     }
     outputs
   }
+
+  /// Lazily steps the driver for each input
+  fn drive<InputIterator>(&mut self, inputs: InputIterator) -> impl Iterator<Item = Output>
+  where
+    InputIterator: IntoIterator<Item = Input>,
+  {
+    inputs.into_iter().map(move |input| self.step(input))
+  }
+
+  /// Steps until an output satisfies `pred` and returns that output
+  fn run_until<InputIterator, P>(&mut self, inputs: InputIterator, mut pred: P) -> Option<Output>
+  where
+    InputIterator: IntoIterator<Item = Input>,
+    P: FnMut(&Output) -> bool,
+  {
+    self.drive(inputs).find(|output| pred(output))
+  }
+
+  /// Steps through all inputs and returns the last output
+  fn run_last<InputIterator>(&mut self, inputs: InputIterator) -> Option<Output>
+  where
+    InputIterator: IntoIterator<Item = Input>,
+  {
+    self.drive(inputs).last()
+  }
+
+  /// Steps through all inputs, folding the outputs into an accumulator
+  fn run_fold<InputIterator, B, F>(&mut self, inputs: InputIterator, init: B, f: F) -> B
+  where
+    InputIterator: IntoIterator<Item = Input>,
+    F: FnMut(B, Output) -> B,
+  {
+    self.drive(inputs).fold(init, f)
+  }
 }
+impl<Input, Output, D: Driver<Input, Output> + ?Sized> DriverExt<Input, Output> for D {}
 
 /// State machine driver with transition table
 ///
 /// Zero-cost construction
+#[cfg(feature = "std")]
 pub struct DriverTransitionTable<'a, State, Input, Output> {
   sm: &'a mut StateMachine<State>,
   tt: &'a HashMap<(State, Input), (State, Output)>,
 }
+#[cfg(feature = "std")]
 impl<'a, State, Input, Output> DriverTransitionTable<'a, State, Input, Output> {
   pub fn new(
     sm: &'a mut StateMachine<State>,
@@ -46,6 +119,7 @@ impl<'a, State, Input, Output> DriverTransitionTable<'a, State, Input, Output> {
   }
 }
 
+#[cfg(feature = "std")]
 impl<'a, State, Input, Output> Driver<Input, Output>
   for DriverTransitionTable<'a, State, Input, Output>
 where
@@ -60,6 +134,88 @@ where
   }
 }
 
+#[cfg(feature = "std")]
+impl<'a, State, Input, Output> TryDriver<Input, Output>
+  for DriverTransitionTable<'a, State, Input, Output>
+where
+  Input: Hash + Eq,
+  State: Copy + Hash + Eq,
+  Output: Copy,
+{
+  type State = State;
+  fn try_step(&mut self, input: Input) -> Result<Output, StepError<State, Input>> {
+    let key = (self.sm.state, input);
+    match self.tt.get(&key) {
+      Some((state, output)) => {
+        self.sm.state = *state;
+        Ok(*output)
+      }
+      None => Err(StepError {
+        state: key.0,
+        input: key.1,
+      }),
+    }
+  }
+}
+
+/// State machine driver with ordered transition table
+///
+/// Like `DriverTransitionTable` without `std`, for `Ord` states and inputs.
+///
+/// Zero-cost construction
+#[cfg(feature = "alloc")]
+pub struct DriverBTreeTable<'a, State, Input, Output> {
+  sm: &'a mut StateMachine<State>,
+  tt: &'a BTreeMap<(State, Input), (State, Output)>,
+}
+#[cfg(feature = "alloc")]
+impl<'a, State, Input, Output> DriverBTreeTable<'a, State, Input, Output> {
+  pub fn new(
+    sm: &'a mut StateMachine<State>,
+    tt: &'a BTreeMap<(State, Input), (State, Output)>,
+  ) -> Self {
+    Self { sm, tt }
+  }
+}
+
+#[cfg(feature = "alloc")]
+impl<'a, State, Input, Output> Driver<Input, Output> for DriverBTreeTable<'a, State, Input, Output>
+where
+  Input: Ord,
+  State: Copy + Ord,
+  Output: Copy,
+{
+  fn step(&mut self, input: Input) -> Output {
+    let (state, output) = self.tt.get(&(self.sm.state, input)).unwrap();
+    self.sm.state = *state;
+    *output
+  }
+}
+
+#[cfg(feature = "alloc")]
+impl<'a, State, Input, Output> TryDriver<Input, Output>
+  for DriverBTreeTable<'a, State, Input, Output>
+where
+  Input: Ord,
+  State: Copy + Ord,
+  Output: Copy,
+{
+  type State = State;
+  fn try_step(&mut self, input: Input) -> Result<Output, StepError<State, Input>> {
+    let key = (self.sm.state, input);
+    match self.tt.get(&key) {
+      Some((state, output)) => {
+        self.sm.state = *state;
+        Ok(*output)
+      }
+      None => Err(StepError {
+        state: key.0,
+        input: key.1,
+      }),
+    }
+  }
+}
+
 /// State machine driver with transition function
 ///
 /// Zero-cost construction
@@ -93,10 +249,51 @@ where
   }
 }
 
+/// Result of a partial transition function
+///
+/// Implemented for `Option<(State, Output)>` and `Result<(State, Output), E>`.
+pub trait PartialTransition<State, Output> {
+  fn into_transition(self) -> Option<(State, Output)>;
+}
+impl<State, Output> PartialTransition<State, Output> for Option<(State, Output)> {
+  fn into_transition(self) -> Option<(State, Output)> {
+    self
+  }
+}
+impl<State, Output, E> PartialTransition<State, Output> for Result<(State, Output), E> {
+  fn into_transition(self) -> Option<(State, Output)> {
+    self.ok()
+  }
+}
+
+impl<'a, State, Input, Output, F, R> TryDriver<Input, Output>
+  for DriverTransitionFunction<'a, State, Input, Output, F>
+where
+  State: Copy,
+  Input: Clone,
+  F: Fn(State, Input) -> R,
+  R: PartialTransition<State, Output>,
+{
+  type State = State;
+  fn try_step(&mut self, input: Input) -> Result<Output, StepError<State, Input>> {
+    match (self.tf)(self.sm.state, input.clone()).into_transition() {
+      Some((state, output)) => {
+        self.sm.state = state;
+        Ok(output)
+      }
+      None => Err(StepError {
+        state: self.sm.state,
+        input,
+      }),
+    }
+  }
+}
+
 #[cfg(test)]
 mod tests {
   use super::*;
 
+  #[cfg(feature = "std")]
   #[test]
   fn turnstile_transition_table() {
     #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
@@ -156,4 +353,130 @@ mod tests {
     assert_eq!(driver.step(Input::Coin), State::Unlocked);
     assert_eq!(driver.step(Input::Push), State::Locked);
   }
+
+  #[cfg(feature = "std")]
+  #[test]
+  fn missing_transition_table() {
+    #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+    enum State {
+      Locked,
+      Unlocked,
+    }
+    #[derive(Debug, PartialEq, Eq, Hash)]
+    enum Input {
+      Push,
+      Coin,
+    }
+    let transition_table = HashMap::from([
+      ((State::Locked, Input::Coin), (State::Unlocked, ())),
+      ((State::Unlocked, Input::Push), (State::Locked, ())),
+    ]);
+
+    let mut state_machine = StateMachine::new(State::Locked);
+    let mut driver = DriverTransitionTable::new(&mut state_machine, &transition_table);
+    assert_eq!(
+      driver.try_step(Input::Push),
+      Err(StepError {
+        state: State::Locked,
+        input: Input::Push,
+      })
+    );
+    assert_eq!(driver.try_step(Input::Coin), Ok(()));
+    assert_eq!(
+      driver.try_step(Input::Coin),
+      Err(StepError {
+        state: State::Unlocked,
+        input: Input::Coin,
+      })
+    );
+    assert_eq!(state_machine.state, State::Unlocked);
+  }
+
+  #[test]
+  fn missing_transition_function() {
+    #[derive(Debug, Copy, Clone, PartialEq)]
+    enum State {
+      Locked,
+      Unlocked,
+    }
+    #[derive(Debug, Clone, PartialEq)]
+    enum Input {
+      Push,
+      Coin,
+    }
+    fn transition_function(state: State, input: Input) -> Option<(State, State)> {
+      match (state, input) {
+        (State::Locked, Input::Coin) => Some((State::Unlocked, State::Unlocked)),
+        (State::Unlocked, Input::Push) => Some((State::Locked, State::Locked)),
+        _ => None,
+      }
+    }
+
+    let mut state_machine = StateMachine::new(State::Locked);
+    let mut driver = DriverTransitionFunction::new(&mut state_machine, &transition_function);
+    assert_eq!(driver.try_step(Input::Coin), Ok(State::Unlocked));
+    let error = driver.try_step(Input::Coin).unwrap_err();
+    assert_eq!(error.state, State::Unlocked);
+    assert_eq!(error.input, Input::Coin);
+    assert_eq!(
+      error.to_string(),
+      "no transition from state Unlocked on input Coin"
+    );
+    assert_eq!(driver.try_step(Input::Push), Ok(State::Locked));
+  }
+
+  #[cfg(feature = "alloc")]
+  #[test]
+  fn counter_driver_ext() {
+    fn transition_function(state: u32, input: u32) -> (u32, u32) {
+      (state + input, state + input)
+    }
+
+    let mut state_machine = StateMachine::new(0);
+    let mut driver = DriverTransitionFunction::new(&mut state_machine, &transition_function);
+    assert_eq!(driver.run([1, 2, 3]), vec![1, 3, 6]);
+    assert_eq!(driver.drive([1, 1]).collect::<Vec<_>>(), vec![7, 8]);
+    assert_eq!(
+      driver.run_until(std::iter::repeat(1), |&n| n >= 20),
+      Some(20)
+    );
+    assert_eq!(driver.run_last([]), None);
+    assert_eq!(driver.run_last([5, 5]), Some(30));
+    assert_eq!(
+      driver.run_fold(0..4, 0, |sum, n| sum + n),
+      30 + 31 + 33 + 36
+    );
+    assert_eq!(state_machine.state, 36);
+  }
+
+  #[cfg(feature = "alloc")]
+  #[test]
+  fn turnstile_btree_table() {
+    #[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+    enum State {
+      Locked,
+      Unlocked,
+    }
+    #[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
+    enum Input {
+      Push,
+      Coin,
+    }
+    let transition_table = BTreeMap::from([
+      ((State::Locked, Input::Coin), (State::Unlocked, "unlock")),
+      ((State::Unlocked, Input::Push), (State::Locked, "lock")),
+    ]);
+
+    let mut state_machine = StateMachine::new(State::Locked);
+    let mut driver = DriverBTreeTable::new(&mut state_machine, &transition_table);
+    assert_eq!(driver.run([Input::Coin, Input::Push]), ["unlock", "lock"]);
+    assert_eq!(
+      driver.try_step(Input::Push),
+      Err(StepError {
+        state: State::Locked,
+        input: Input::Push,
+      })
+    );
+    assert_eq!(state_machine.state, State::Locked);
+  }
 }
@@ -0,0 +1,834 @@
+use crate::{Driver, StateMachine, StepError, TryDriver};
+use std::{collections::HashMap, hash::Hash};
+
+#[derive(Debug, Clone)]
+struct Node<State> {
+  parent: Option<State>,
+  /// Declaration order, parents come before their substates
+  order: usize,
+  children: Vec<State>,
+  /// Initial substate of compound states
+  initial: Option<State>,
+  /// Substates are orthogonal regions that are all active together
+  parallel: bool,
+  /// History pseudo-states of this state
+  histories: Vec<State>,
+  /// Kind and default target of a history pseudo-state
+  history: Option<(History, State)>,
+}
+
+/// History pseudo-state kind
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum History {
+  /// Resumes the substates active on exit, entering them by default
+  Shallow,
+  /// Resumes the leaf states active on exit
+  Deep,
+}
+
+/// Active leaf states of a statechart in declaration order, together with recorded history
+///
+/// A single state without parallel states, one leaf per active region otherwise.
+#[derive(Debug, Clone)]
+#[cfg_attr(
+  feature = "serde",
+  derive(serde::Serialize, serde::Deserialize),
+  serde(bound(
+    serialize = "State: serde::Serialize",
+    deserialize = "State: serde::Deserialize<'de> + Hash + Eq"
+  ))
+)]
+pub struct Configuration<State> {
+  leaves: Vec<State>,
+  history: HashMap<State, Vec<State>>,
+}
+impl<State> Configuration<State> {
+  pub fn leaves(&self) -> &[State] {
+    &self.leaves
+  }
+
+  /// States recorded per history pseudo-state on the last exit of its parent
+  pub fn history(&self) -> &HashMap<State, Vec<State>> {
+    &self.history
+  }
+}
+
+/// Transition action on the extended state and the event
+pub type Action<Context, Event> = Box<dyn Fn(&mut Context, &Event)>;
+
+/// Entry or exit action on the extended state
+///
+/// Without the event, since starting the statechart enters states on no event.
+pub type StateAction<Context> = Box<dyn Fn(&mut Context)>;
+
+/// Hierarchical state machine
+///
+/// States form a tree below an implicit root. Compound states are entered through
+/// their initial substate, parallel states through all of their substates at once.
+/// An event the active state does not handle bubbles up to its ancestors, every active region
+/// receives it. Transitions are external, the source state is always exited and the target entered.
+///
+/// Actions run on a `Context` holding the extended state. A transition runs the exit actions
+/// innermost first, then its own action, then the entry actions outermost first.
+pub struct Statechart<State, Event, Output, Context = ()> {
+  initial: State,
+  nodes: HashMap<State, Node<State>>,
+  transitions: HashMap<(State, Event), (State, Output)>,
+  entry_actions: HashMap<State, StateAction<Context>>,
+  exit_actions: HashMap<State, StateAction<Context>>,
+  transition_actions: HashMap<(State, Event), Action<Context, Event>>,
+}
+impl<State, Event, Output, Context> Statechart<State, Event, Output, Context>
+where
+  State: Copy + Hash + Eq,
+{
+  /// Statechart starting in the top-level state `initial`
+  pub fn new(initial: State) -> Self {
+    let mut chart = Self {
+      initial,
+      nodes: HashMap::new(),
+      transitions: HashMap::new(),
+      entry_actions: HashMap::new(),
+      exit_actions: HashMap::new(),
+      transition_actions: HashMap::new(),
+    };
+    chart.insert(initial, None, false, None);
+    chart
+  }
+
+  fn insert(
+    &mut self,
+    state: State,
+    parent: Option<State>,
+    parallel: bool,
+    history: Option<(History, State)>,
+  ) {
+    if let Some(parent) = parent {
+      let parent = self.nodes.get_mut(&parent).expect("unknown parent state");
+      if history.is_some() {
+        parent.histories.push(state);
+      } else {
+        parent.children.push(state);
+        if !parent.parallel {
+          parent.initial.get_or_insert(state);
+        }
+      }
+    }
+    let node = Node {
+      parent,
+      order: self.nodes.len(),
+      children: Vec::new(),
+      initial: None,
+      parallel,
+      histories: Vec::new(),
+      history,
+    };
+    assert!(self.nodes.insert(state, node).is_none(), "duplicate state");
+  }
+
+  /// Adds `state` as substate of `parent`, or as top-level state
+  ///
+  /// The first substate of a compound state is its initial substate.
+  ///
+  /// # Panics
+  ///
+  /// If `state` already exists or `parent` does not.
+  pub fn add_state(&mut self, state: State, parent: Option<State>) {
+    self.insert(state, parent, false, None);
+  }
+
+  /// Adds a parallel state, each of its substates is an orthogonal region
+  ///
+  /// # Panics
+  ///
+  /// If `state` already exists or `parent` does not.
+  pub fn add_parallel(&mut self, state: State, parent: Option<State>) {
+    self.insert(state, parent, true, None);
+  }
+
+  /// Adds a history pseudo-state of `parent` as transition target
+  ///
+  /// Targeting it resumes the substates of `parent` recorded on its last exit,
+  /// or enters `default` if `parent` was never exited.
+  ///
+  /// # Panics
+  ///
+  /// If `state` already exists or `default` is no substate of `parent`.
+  pub fn add_history(&mut self, state: State, parent: State, kind: History, default: State) {
+    assert!(
+      self.ancestors(default).skip(1).any(|state| state == parent),
+      "default is no substate of the parent"
+    );
+    self.insert(state, Some(parent), false, Some((kind, default)));
+  }
+
+  /// Makes `substate` the initial substate of its parent
+  ///
+  /// # Panics
+  ///
+  /// If `substate` is unknown, a top-level state or a region of a parallel state.
+  pub fn set_initial(&mut self, substate: State) {
+    let parent = self.nodes[&substate].parent.expect("top-level state");
+    let parent = self.nodes.get_mut(&parent).unwrap();
+    assert!(!parent.parallel, "region of a parallel state");
+    parent.initial = Some(substate);
+  }
+
+  /// Adds a transition handling `event` in `from` and all its substates
+  ///
+  /// # Panics
+  ///
+  /// If `from` or `to` is unknown.
+  pub fn add_transition(&mut self, from: State, event: Event, to: State, output: Output)
+  where
+    Event: Hash + Eq,
+  {
+    assert!(
+      self.nodes.contains_key(&from) && self.nodes.contains_key(&to),
+      "unknown state"
+    );
+    self.transitions.insert((from, event), (to, output));
+  }
+
+  /// Adds a transition that runs `action` between exiting and entering states
+  ///
+  /// # Panics
+  ///
+  /// If `from` or `to` is unknown.
+  pub fn add_transition_with(
+    &mut self,
+    from: State,
+    event: Event,
+    to: State,
+    output: Output,
+    action: impl Fn(&mut Context, &Event) + 'static,
+  ) where
+    Event: Clone + Hash + Eq,
+  {
+    self.add_transition(from, event.clone(), to, output);
+    self
+      .transition_actions
+      .insert((from, event), Box::new(action));
+  }
+
+  /// Runs `action` whenever `state` is entered, replacing a previous entry action
+  pub fn on_entry(&mut self, state: State, action: impl Fn(&mut Context) + 'static) {
+    self.entry_actions.insert(state, Box::new(action));
+  }
+
+  /// Runs `action` whenever `state` is exited, replacing a previous exit action
+  pub fn on_exit(&mut self, state: State, action: impl Fn(&mut Context) + 'static) {
+    self.exit_actions.insert(state, Box::new(action));
+  }
+
+  pub fn initial(&self) -> &State {
+    &self.initial
+  }
+
+  pub fn transitions(&self) -> &HashMap<(State, Event), (State, Output)> {
+    &self.transitions
+  }
+
+  pub fn parent(&self, state: &State) -> Option<State> {
+    self.nodes[state].parent
+  }
+
+  pub fn is_composite(&self, state: &State) -> bool {
+    !self.nodes[state].children.is_empty()
+  }
+
+  pub fn is_parallel(&self, state: &State) -> bool {
+    self.nodes[state].parallel
+  }
+
+  /// `state` followed by its ancestors up to the top level
+  pub fn ancestors(&self, state: State) -> impl Iterator<Item = State> + '_ {
+    std::iter::successors(Some(state), |state| self.parent(state))
+  }
+
+  /// Configuration entered on start
+  pub fn initial_configuration(&self) -> Configuration<State> {
+    let mut entries = Vec::new();
+    self.enter(self.initial, &mut entries);
+    self.configuration(entries, HashMap::new())
+  }
+
+  /// Active leaves and all their ancestors in declaration order
+  pub fn active_states(&self, configuration: &Configuration<State>) -> Vec<State> {
+    let mut active = Vec::new();
+    for &leaf in &configuration.leaves {
+      for state in self.ancestors(leaf) {
+        if active.contains(&state) {
+          break;
+        }
+        active.push(state);
+      }
+    }
+    self.sort(&mut active);
+    active
+  }
+
+  fn sort(&self, states: &mut [State]) {
+    states.sort_by_key(|state| self.nodes[state].order);
+  }
+
+  fn configuration(
+    &self,
+    mut states: Vec<State>,
+    history: HashMap<State, Vec<State>>,
+  ) -> Configuration<State> {
+    states.retain(|state| !self.is_composite(state));
+    self.sort(&mut states);
+    Configuration {
+      leaves: states,
+      history,
+    }
+  }
+
+  /// States a transition to `target` enters besides their ancestors
+  ///
+  /// Resolves history pseudo-states to their recorded states or their default.
+  fn targets(&self, configuration: &Configuration<State>, target: State) -> Vec<State> {
+    match self.nodes[&target].history {
+      Some((_, default)) => configuration
+        .history
+        .get(&target)
+        .cloned()
+        .unwrap_or_else(|| vec![default]),
+      None => vec![target],
+    }
+  }
+
+  /// Records the history of exited states from the `active` states before the exit
+  fn record(&self, active: &[State], exits: &[State], history: &mut HashMap<State, Vec<State>>) {
+    for exited in exits {
+      for &pseudo in &self.nodes[exited].histories {
+        let recorded = active
+          .iter()
+          .copied()
+          .filter(|state| match self.nodes[&pseudo].history {
+            Some((History::Shallow, _)) => self.parent(state) == Some(*exited),
+            _ => !self.is_composite(state) && self.ancestors(*state).skip(1).any(|s| s == *exited),
+          });
+        history.insert(pseudo, recorded.collect());
+      }
+    }
+  }
+
+  /// Enters `state` and its default substates
+  fn enter(&self, state: State, entries: &mut Vec<State>) {
+    entries.push(state);
+    self.enter_substates(state, entries);
+  }
+
+  fn enter_substates(&self, state: State, entries: &mut Vec<State>) {
+    let node = &self.nodes[&state];
+    match node.parallel {
+      true => node
+        .children
+        .iter()
+        .for_each(|&child| self.enter(child, entries)),
+      false => node
+        .initial
+        .into_iter()
+        .for_each(|child| self.enter(child, entries)),
+    }
+  }
+
+  /// States exited and entered by a transition from `source` to `target` in `configuration`
+  ///
+  /// History pseudo-states resolve to the recorded states or their default first.
+  /// The transition domain is the least common proper ancestor of `source` and the targets that
+  /// is no parallel state, so a transition between regions leaves the whole parallel state.
+  /// Every active state below the domain is exited, innermost first. Entries run outermost first
+  /// from the domain down to the targets and on through default substates, including the other
+  /// regions of parallel states on the way.
+  pub fn transition_path(
+    &self,
+    configuration: &Configuration<State>,
+    source: State,
+    target: State,
+  ) -> (Vec<State>, Vec<State>) {
+    let targets = self.targets(configuration, target);
+    let domain = self.ancestors(source).skip(1).find(|&domain| {
+      !self.is_parallel(&domain)
+        && targets
+          .iter()
+          .all(|&target| self.ancestors(target).skip(1).any(|state| state == domain))
+    });
+    let below_domain = |state: &State| {
+      domain.is_none_or(|domain| self.ancestors(*state).skip(1).any(|state| state == domain))
+    };
+
+    let mut exits = self.active_states(configuration);
+    exits.retain(below_domain);
+    exits.reverse();
+
+    let mut path: Vec<State> = Vec::new();
+    for &target in &targets {
+      for state in self.ancestors(target) {
+        if Some(state) == domain || path.contains(&state) {
+          break;
+        }
+        path.push(state);
+      }
+    }
+    let mut entries = path.clone();
+    for state in &path {
+      if self.is_parallel(state) && !targets.contains(state) {
+        for &region in &self.nodes[state].children {
+          if !path.contains(&region) {
+            self.enter(region, &mut entries);
+          }
+        }
+      }
+    }
+    for &target in &targets {
+      self.enter_substates(target, &mut entries);
+    }
+    self.sort(&mut entries);
+    (exits, entries)
+  }
+}
+
+/// Statechart driver
+///
+/// The state machine holds the configuration of active leaves. Every active region handles an
+/// input at most once, [`step_all`](Self::step_all) outputs those of the transitions taken in
+/// declaration order of the leaves. As a [`Driver`] it outputs the first of them.
+/// A transition is skipped if it would exit a state already exited by an earlier one in the same step.
+/// Transitions taken in the same step run their actions one transition after another.
+/// The driver owns the context the actions run on.
+///
+/// Zero-cost construction
+pub struct DriverStatechart<'a, State, Event, Output, Context = ()> {
+  sm: &'a mut StateMachine<Configuration<State>>,
+  chart: &'a Statechart<State, Event, Output, Context>,
+  context: Context,
+}
+impl<'a, State, Event, Output> DriverStatechart<'a, State, Event, Output> {
+  pub fn new(
+    sm: &'a mut StateMachine<Configuration<State>>,
+    chart: &'a Statechart<State, Event, Output>,
+  ) -> Self {
+    Self {
+      sm,
+      chart,
+      context: (),
+    }
+  }
+}
+impl<'a, State, Event, Output, Context> DriverStatechart<'a, State, Event, Output, Context>
+where
+  State: Copy + Hash + Eq,
+{
+  pub fn with_context(
+    sm: &'a mut StateMachine<Configuration<State>>,
+    chart: &'a Statechart<State, Event, Output, Context>,
+    context: Context,
+  ) -> Self {
+    Self { sm, chart, context }
+  }
+
+  pub fn context(&self) -> &Context {
+    &self.context
+  }
+
+  pub fn context_mut(&mut self) -> &mut Context {
+    &mut self.context
+  }
+
+  pub fn into_context(self) -> Context {
+    self.context
+  }
+
+  /// Enters the initial configuration, running the entry actions
+  ///
+  /// Constructing the driver runs no actions, the state machine may hold a restored configuration.
+  pub fn start(&mut self) {
+    let mut entries = Vec::new();
+    self.chart.enter(self.chart.initial, &mut entries);
+    self.chart.sort(&mut entries);
+    self.run_actions(&entries, &self.chart.entry_actions);
+    self.sm.state = self.chart.configuration(entries, HashMap::new());
+  }
+
+  fn run_actions(&mut self, states: &[State], actions: &HashMap<State, StateAction<Context>>) {
+    for state in states {
+      if let Some(action) = actions.get(state) {
+        action(&mut self.context);
+      }
+    }
+  }
+
+  /// Whether `state` is an active leaf or one of their ancestors
+  pub fn is_in(&self, state: &State) -> bool {
+    self
+      .sm
+      .state
+      .leaves
+      .iter()
+      .any(|&leaf| self.chart.ancestors(leaf).any(|active| active == *state))
+  }
+}
+
+impl<'a, State, Event, Output, Context> DriverStatechart<'a, State, Event, Output, Context>
+where
+  State: Copy + Hash + Eq,
+  Event: Clone + Hash + Eq,
+  Output: Copy,
+{
+  /// Broadcasts `input` to every active region, outputs of all transitions taken
+  pub fn step_all(&mut self, input: Event) -> Vec<Output> {
+    let mut exited = Vec::new();
+    let mut taken = Vec::new();
+    for &leaf in &self.sm.state.leaves {
+      let Some((source, (target, output))) = self.chart.ancestors(leaf).find_map(|state| {
+        let transition = self.chart.transitions.get(&(state, input.clone()));
+        transition.map(|transition| (state, transition))
+      }) else {
+        continue;
+      };
+      let (exits, entries) = self.chart.transition_path(&self.sm.state, source, *target);
+      if exits.iter().any(|state| exited.contains(state)) {
+        continue;
+      }
+      exited.extend(exits.iter().copied());
+      let action = self.chart.transition_actions.get(&(source, input.clone()));
+      taken.push((exits, entries, *output, action));
+    }
+    if taken.is_empty() {
+      return Vec::new();
+    }
+
+    let mut active = self.chart.active_states(&self.sm.state);
+    let mut history = std::mem::take(&mut self.sm.state.history);
+    let mut outputs = Vec::new();
+    for (exits, entries, output, action) in taken {
+      self.run_actions(&exits, &self.chart.exit_actions);
+      if let Some(action) = action {
+        action(&mut self.context, &input);
+      }
+      self.run_actions(&entries, &self.chart.entry_actions);
+      self.chart.record(&active, &exits, &mut history);
+      active.retain(|state| !exits.contains(state));
+      active.extend(entries);
+      outputs.push(output);
+    }
+    self.sm.state = self.chart.configuration(active, history);
+    outputs
+  }
+}
+
+impl<'a, State, Event, Output, Context> Driver<Event, Output>
+  for DriverStatechart<'a, State, Event, Output, Context>
+where
+  State: Copy + Hash + Eq,
+  Event: Clone + Hash + Eq,
+  Output: Copy,
+{
+  fn step(&mut self, input: Event) -> Output {
+    self.step_all(input)[0]
+  }
+}
+
+impl<'a, State, Event, Output, Context> TryDriver<Event, Output>
+  for DriverStatechart<'a, State, Event, Output, Context>
+where
+  State: Copy + Hash + Eq,
+  Event: Clone + Hash + Eq,
+  Output: Copy,
+{
+  type State = Configuration<State>;
+  /// Output of the first transition taken, see [`step_all`](Self::step_all) for parallel states
+  fn try_step(&mut self, input: Event) -> Result<Output, StepError<Self::State, Event>> {
+    match self.step_all(input.clone()).first() {
+      Some(&output) => Ok(output),
+      None => Err(StepError {
+        state: self.sm.state.clone(),
+        input,
+      }),
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::DriverExt;
+
+  #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+  enum State {
+    LoggedOut,
+    LoggedIn,
+    Browsing,
+    Editing,
+    Draft,
+    Preview,
+  }
+  use State::*;
+
+  fn chart() -> Statechart<State, &'static str, &'static str> {
+    let mut chart = Statechart::new(LoggedOut);
+    chart.add_state(LoggedIn, None);
+    chart.add_state(Browsing, Some(LoggedIn));
+    chart.add_state(Editing, Some(LoggedIn));
+    chart.add_state(Draft, Some(Editing));
+    chart.add_state(Preview, Some(Editing));
+    chart.add_transition(LoggedOut, "login", LoggedIn, "welcome");
+    chart.add_transition(LoggedIn, "logout", LoggedOut, "bye");
+    chart.add_transition(Browsing, "edit", Editing, "open");
+    chart.add_transition(Editing, "close", Browsing, "closed");
+    chart.add_transition(Draft, "preview", Preview, "render");
+    chart.add_transition(Preview, "edit", Draft, "back");
+    chart
+  }
+
+  #[test]
+  fn event_bubbling() {
+    let chart = chart();
+    let mut state_machine = StateMachine::new(chart.initial_configuration());
+    let mut driver = DriverStatechart::new(&mut state_machine, &chart);
+    let error = driver.try_step("edit").unwrap_err();
+    assert_eq!(
+      (error.state.leaves(), error.input),
+      ([LoggedOut].as_slice(), "edit")
+    );
+    assert_eq!(
+      driver.run(["login", "edit", "preview", "edit", "close"]),
+      ["welcome", "open", "render", "back", "closed"]
+    );
+    assert!(driver.is_in(&LoggedIn));
+    assert_eq!(driver.run(["edit", "logout"]), ["open", "bye"]);
+    assert!(!driver.is_in(&LoggedIn));
+    assert_eq!(state_machine.state().leaves(), [LoggedOut]);
+  }
+
+  #[test]
+  fn action_order() {
+    let mut chart: Statechart<State, &str, (), Vec<&str>> = Statechart::new(LoggedOut);
+    chart.add_state(LoggedIn, None);
+    chart.add_state(Browsing, Some(LoggedIn));
+    chart.add_state(Editing, Some(LoggedIn));
+    chart.add_state(Draft, Some(Editing));
+    chart.add_transition(LoggedOut, "login", LoggedIn, ());
+    chart.add_transition(Browsing, "edit", Editing, ());
+    let label = "action";
+    chart.add_transition_with(Editing, "logout", LoggedOut, (), move |log, event| {
+      log.extend([label, *event])
+    });
+    chart.on_entry(LoggedOut, |log| log.push("enter LoggedOut"));
+    chart.on_exit(LoggedOut, |log| log.push("exit LoggedOut"));
+    chart.on_entry(LoggedIn, |log| log.push("enter LoggedIn"));
+    chart.on_exit(LoggedIn, |log| log.push("exit LoggedIn"));
+    chart.on_entry(Browsing, |log| log.push("enter Browsing"));
+    chart.on_exit(Editing, |log| log.push("exit Editing"));
+    chart.on_exit(Draft, |log| log.push("exit Draft"));
+
+    let mut state_machine = StateMachine::new(chart.initial_configuration());
+    let mut driver = DriverStatechart::with_context(&mut state_machine, &chart, Vec::new());
+    driver.start();
+    driver.run(["login", "edit", "logout"]);
+    assert_eq!(
+      driver.into_context(),
+      [
+        "enter LoggedOut",
+        "exit LoggedOut",
+        "enter LoggedIn",
+        "enter Browsing",
+        "exit Draft",
+        "exit Editing",
+        "exit LoggedIn",
+        "action",
+        "logout",
+        "enter LoggedOut"
+      ]
+    );
+  }
+
+  #[test]
+  fn least_common_ancestor() {
+    let chart = chart();
+    let at = |leaf| Configuration {
+      leaves: vec![leaf],
+      history: HashMap::new(),
+    };
+    assert_eq!(
+      chart.transition_path(&at(Preview), LoggedIn, LoggedOut),
+      (vec![Preview, Editing, LoggedIn], vec![LoggedOut])
+    );
+    assert_eq!(
+      chart.transition_path(&at(Preview), Editing, Browsing),
+      (vec![Preview, Editing], vec![Browsing])
+    );
+    assert_eq!(
+      chart.transition_path(&at(Draft), Draft, Preview),
+      (vec![Draft], vec![Preview])
+    );
+    assert_eq!(
+      chart.transition_path(&at(LoggedOut), LoggedOut, LoggedIn),
+      (vec![LoggedOut], vec![LoggedIn, Browsing])
+    );
+    // external self transition and transition into a substate
+    assert_eq!(
+      chart.transition_path(&at(Preview), Editing, Editing),
+      (vec![Preview, Editing], vec![Editing, Draft])
+    );
+    assert_eq!(
+      chart.transition_path(&at(Browsing), LoggedIn, Preview),
+      (vec![Browsing, LoggedIn], vec![LoggedIn, Editing, Preview])
+    );
+  }
+
+  #[test]
+  fn orthogonal_regions() {
+    #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+    enum Device {
+      Off,
+      Running,
+      Power,
+      Battery,
+      Charging,
+      Connectivity,
+      Offline,
+      Online,
+    }
+    use Device::*;
+    let mut chart = Statechart::new(Off);
+    chart.add_parallel(Running, None);
+    chart.add_state(Power, Some(Running));
+    chart.add_state(Battery, Some(Power));
+    chart.add_state(Charging, Some(Power));
+    chart.add_state(Connectivity, Some(Running));
+    chart.add_state(Offline, Some(Connectivity));
+    chart.add_state(Online, Some(Connectivity));
+    chart.add_transition(Off, "boot", Running, "booted");
+    chart.add_transition(Off, "sync", Online, "syncing");
+    chart.add_transition(Battery, "plug", Charging, "charging");
+    chart.add_transition(Offline, "toggle", Online, "online");
+    chart.add_transition(Online, "toggle", Offline, "offline");
+    chart.add_transition(Charging, "toggle", Battery, "unplugged");
+    chart.add_transition(Battery, "roam", Online, "roaming");
+    chart.add_transition(Running, "shutdown", Off, "off");
+
+    let mut state_machine = StateMachine::new(chart.initial_configuration());
+    let mut driver = DriverStatechart::new(&mut state_machine, &chart);
+    assert_eq!(driver.step_all("boot"), ["booted"]);
+    assert!(driver.is_in(&Running) && driver.is_in(&Battery) && driver.is_in(&Offline));
+    assert_eq!(driver.step_all("toggle"), ["online"]);
+    assert_eq!(driver.step_all("plug"), ["charging"]);
+    // broadcast to both regions
+    assert_eq!(driver.step_all("toggle"), ["unplugged", "offline"]);
+    assert_eq!(state_machine.state().leaves(), [Battery, Offline]);
+
+    // across regions the parallel state is exited and all regions are entered again
+    assert_eq!(
+      chart.transition_path(state_machine.state(), Battery, Online),
+      (
+        vec![Offline, Connectivity, Battery, Power, Running],
+        vec![Running, Power, Battery, Connectivity, Online]
+      )
+    );
+    let mut driver = DriverStatechart::new(&mut state_machine, &chart);
+    assert_eq!(driver.step_all("roam"), ["roaming"]);
+    assert_eq!(state_machine.state().leaves(), [Battery, Online]);
+    assert_eq!(
+      chart.transition_path(state_machine.state(), Off, Running).1,
+      [Running, Power, Battery, Connectivity, Offline]
+    );
+
+    // leaving the whole parallel state handles the event once
+    let mut driver = DriverStatechart::new(&mut state_machine, &chart);
+    assert_eq!(driver.step_all("shutdown"), ["off"]);
+    assert_eq!(state_machine.state().leaves(), [Off]);
+    assert_eq!(
+      chart.transition_path(state_machine.state(), Off, Online),
+      (
+        vec![Off],
+        vec![Running, Power, Battery, Connectivity, Online]
+      )
+    );
+    let mut driver = DriverStatechart::new(&mut state_machine, &chart);
+    assert_eq!(driver.step_all("sync"), ["syncing"]);
+    assert!(driver.step_all("boot").is_empty());
+    // a single region handles the event
+    assert_eq!(driver.step("toggle"), "offline");
+    assert_eq!(state_machine.state().leaves(), [Battery, Offline]);
+  }
+
+  #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+  #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+  enum App {
+    Home,
+    Editor,
+    Text,
+    Image,
+    Crop,
+    Filter,
+    Shallow,
+    Deep,
+  }
+
+  fn editor() -> Statechart<App, &'static str, ()> {
+    use App::*;
+    let mut chart = Statechart::new(Home);
+    chart.add_state(Editor, None);
+    chart.add_state(Text, Some(Editor));
+    chart.add_state(Image, Some(Editor));
+    chart.add_state(Crop, Some(Image));
+    chart.add_state(Filter, Some(Image));
+    chart.add_history(Shallow, Editor, History::Shallow, Text);
+    chart.add_history(Deep, Editor, History::Deep, Image);
+    chart.add_transition(Home, "shallow", Shallow, ());
+    chart.add_transition(Home, "deep", Deep, ());
+    chart.add_transition(Text, "image", Image, ());
+    chart.add_transition(Crop, "filter", Filter, ());
+    chart.add_transition(Editor, "home", Home, ());
+    chart
+  }
+
+  #[test]
+  fn history() {
+    use App::*;
+    let chart = editor();
+    let mut state_machine = StateMachine::new(chart.initial_configuration());
+    let mut driver = DriverStatechart::new(&mut state_machine, &chart);
+    // defaults without history
+    driver.step("deep");
+    assert!(driver.is_in(&Crop));
+    driver.run(["home", "shallow"]);
+    assert!(driver.is_in(&Image) && driver.is_in(&Crop));
+    driver.run(["home", "shallow", "filter"]);
+    assert!(driver.is_in(&Filter));
+
+    driver.step("home");
+    assert_eq!(state_machine.state().history()[&Shallow], [Image]);
+    assert_eq!(state_machine.state().history()[&Deep], [Filter]);
+    assert_eq!(
+      chart.transition_path(state_machine.state(), Home, Deep),
+      (vec![Home], vec![Editor, Image, Filter])
+    );
+    let mut driver = DriverStatechart::new(&mut state_machine, &chart);
+    driver.step("shallow");
+    assert!(driver.is_in(&Crop));
+    driver.run(["filter", "home", "deep"]);
+    assert!(driver.is_in(&Filter));
+  }
+
+  #[cfg(feature = "serde")]
+  #[test]
+  fn history_snapshot() {
+    use App::*;
+    let chart = editor();
+    let mut state_machine = StateMachine::new(chart.initial_configuration());
+    let mut driver = DriverStatechart::new(&mut state_machine, &chart);
+    driver.run(["deep", "filter", "home"]);
+
+    let snapshot = serde_json::to_string(&state_machine).unwrap();
+    let mut restored: StateMachine<Configuration<App>> = serde_json::from_str(&snapshot).unwrap();
+    assert_eq!(restored.state().leaves(), [Home]);
+    let mut driver = DriverStatechart::new(&mut restored, &chart);
+    driver.step("deep");
+    assert!(driver.is_in(&Filter));
+  }
+}